@@ -0,0 +1,60 @@
+/*
+ * Small smoke test for the C interface.
+ *
+ *   cargo build --release
+ *   cc examples/c/test_thimblerigger.c -Iinclude -Ltarget/release -lthimblerigger -o test_thimblerigger
+ *   LD_LIBRARY_PATH=target/release ./test_thimblerigger
+ */
+#include <stdio.h>
+#include <string.h>
+
+#include "thimblerigger.h"
+
+static int failures = 0;
+
+static void check(int condition, const char *what) {
+    if (!condition) {
+        fprintf(stderr, "FAILED: %s\n", what);
+        failures++;
+    }
+}
+
+int main(void) {
+    ThimbleSolver *solver = thimble_solver_new(1 << 16);
+    check(solver != NULL, "solver created");
+
+    /* the player to move has to block column 4 */
+    const char *moves = "53233651363321727745271";
+    int score = 0;
+    int best_move = 0;
+    int code = thimble_solve(solver, moves, &score, &best_move);
+    check(code == THIMBLE_OK, "solve succeeds");
+    check(best_move == 4, "solve blocks the threat");
+
+    int scores[THIMBLE_WIDTH];
+    code = thimble_analyze(solver, moves, scores);
+    check(code == THIMBLE_OK, "analyze succeeds");
+    check(scores[3] == score, "blocking column has the best score");
+    for (int col = 0; col < THIMBLE_WIDTH; col++) {
+        check(scores[col] == THIMBLE_NO_SCORE || scores[col] <= score, "no column beats the best move");
+    }
+
+    code = thimble_solve(solver, "448", &score, &best_move);
+    check(code == THIMBLE_INVALID_COLUMN, "invalid column is rejected");
+    check(strcmp(thimble_error_message(code), "invalid column") == 0, "error message");
+
+    code = thimble_analyze(solver, "1111111", scores);
+    check(code == THIMBLE_COLUMN_FULL, "full column is rejected");
+
+    code = thimble_analyze(solver, "1212121", scores);
+    check(code == THIMBLE_GAME_OVER, "finished game is rejected");
+
+    check(thimble_solver_clear(solver) == THIMBLE_OK, "clear");
+    thimble_solver_free(solver);
+    thimble_solver_free(NULL);
+
+    if (failures == 0) {
+        printf("all checks passed\n");
+    }
+    return failures == 0 ? 0 : 1;
+}
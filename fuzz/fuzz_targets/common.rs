@@ -0,0 +1,37 @@
+// invariants every board should keep, however it was made
+use thimblerigger::board::Board;
+
+pub fn check(board: &Board) {
+    let (first, second) = (board.stones(0), board.stones(1));
+    assert_eq!(first & second, 0, "{:?}", board);
+    let stones = first | second;
+    assert_eq!(stones & !Board::board_mask(), 0, "{:?}", board);
+    assert_eq!(stones.count_ones(), board.nb_moves() as u32, "{:?}", board);
+    assert_eq!(
+        first.count_ones(),
+        second.count_ones() + board.nb_moves() as u32 % 2,
+        "{:?}",
+        board
+    );
+    for col in 0..Board::WIDTH {
+        for row in 1..Board::HEIGHT {
+            if stones & Board::square_mask(col, row) != 0 {
+                assert_ne!(stones & Board::square_mask(col, row - 1), 0, "{:?}", board);
+            }
+        }
+    }
+    assert_eq!(board.mirror().mirror(), *board);
+}
+
+// for boards from a game that stops at the first four
+pub fn check_game(board: &Board) {
+    check(board);
+    let fours = (Board::fours(board.stones(0)), Board::fours(board.stones(1)));
+    assert!(fours.0 == 0 || fours.1 == 0, "two winners {:?}", board);
+    // only the player who just moved can have won
+    assert!(
+        Board::fours(board.stones(board.nb_moves() % 2)) == 0,
+        "{:?}",
+        board
+    );
+}
@@ -0,0 +1,17 @@
+#![no_main]
+// arbitrary text through construct, and through parse when it's a legal game
+
+use libfuzzer_sys::fuzz_target;
+use thimblerigger::board::Board;
+
+mod common;
+
+fuzz_target!(|data: &[u8]| {
+    let text = String::from_utf8_lossy(data);
+    let board = Board::construct(&text);
+    common::check(&board);
+    if let Ok(parsed) = Board::parse(&text) {
+        common::check_game(&parsed);
+        assert_eq!(parsed, board);
+    }
+});
@@ -0,0 +1,15 @@
+#![no_main]
+// arbitrary diagrams through the grid parser, whatever it accepts has to read back
+
+use libfuzzer_sys::fuzz_target;
+use thimblerigger::board::Board;
+
+mod common;
+
+fuzz_target!(|data: &[u8]| {
+    let text = String::from_utf8_lossy(data);
+    if let Ok(board) = Board::from_grid(&text) {
+        common::check_game(&board);
+        assert_eq!(Board::from_grid(&board.to_string()), Ok(board));
+    }
+});
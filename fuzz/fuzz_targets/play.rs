@@ -0,0 +1,33 @@
+#![no_main]
+// each byte is a column, out of range ones included, played when the board allows it
+
+use libfuzzer_sys::fuzz_target;
+use thimblerigger::board::Board;
+
+mod common;
+
+fuzz_target!(|data: &[u8]| {
+    let mut board = Board::new();
+    let mut moves = String::new();
+    for &byte in data {
+        let col = byte % 16;
+        if col >= Board::WIDTH {
+            assert!(!board.can_play(col));
+            continue;
+        }
+        if board.is_game_over() {
+            continue;
+        }
+        if !board.can_play(col) {
+            let top = Board::square_mask(col, Board::HEIGHT - 1);
+            assert_ne!((board.stones(0) | board.stones(1)) & top, 0, "{:?}", board);
+            continue;
+        }
+        let wins = board.is_winning_move(col);
+        board.play_col(col);
+        moves.push((b'1' + col) as char);
+        assert_eq!(board.last_move_won(), wins);
+        common::check_game(&board);
+    }
+    assert_eq!(Board::parse(&moves), Ok(board));
+});
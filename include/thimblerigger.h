@@ -0,0 +1,89 @@
+#ifndef THIMBLERIGGER_H
+#define THIMBLERIGGER_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit by hand. */
+
+#include <stdint.h>
+
+#define THIMBLE_OK 0
+
+#define THIMBLE_NULL_POINTER 1
+
+#define THIMBLE_INVALID_UTF8 2
+
+#define THIMBLE_INVALID_COLUMN 3
+
+#define THIMBLE_COLUMN_FULL 4
+
+#define THIMBLE_GAME_OVER 5
+
+/**
+ * Number of columns, the length of the array filled by `thimble_analyze`.
+ */
+#define THIMBLE_WIDTH 7
+
+/**
+ * Score reported by `thimble_analyze` for a column that can't be played.
+ */
+#define THIMBLE_NO_SCORE -1000
+
+/**
+ * Opaque solver handle owning a transposition table. Reuse it between calls to keep the cache warm.
+ */
+typedef struct ThimbleSolver ThimbleSolver;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Creates a solver whose table holds `table_capacity` entries, or the default size when zero.
+ * The handle must be released with `thimble_solver_free`.
+ */
+struct ThimbleSolver *thimble_solver_new(uint64_t table_capacity);
+
+/**
+ * Releases a solver, passing null is a no-op.
+ *
+ * # Safety
+ * `solver` must be null or a handle from `thimble_solver_new` that hasn't been freed.
+ */
+void thimble_solver_free(struct ThimbleSolver *solver);
+
+/**
+ * Solves the position reached by `moves`, a nul terminated string of 1 indexed columns.
+ * On success writes the score for the player to move and the best column (1 indexed).
+ *
+ * # Safety
+ * `solver` must be a live handle, `moves` a nul terminated string and the outputs writable.
+ */
+int thimble_solve(struct ThimbleSolver *solver, const char *moves, int *score, int *best_move);
+
+/**
+ * Scores every column of the position reached by `moves`, writing `THIMBLE_WIDTH` values to
+ * `scores`. Full columns get `THIMBLE_NO_SCORE`.
+ *
+ * # Safety
+ * `solver` must be a live handle, `moves` a nul terminated string and `scores` must have room
+ * for `THIMBLE_WIDTH` values.
+ */
+int thimble_analyze(struct ThimbleSolver *solver, const char *moves, int *scores);
+
+/**
+ * Clears the solver's transposition table.
+ *
+ * # Safety
+ * `solver` must be a live handle.
+ */
+int thimble_solver_clear(struct ThimbleSolver *solver);
+
+/**
+ * Static description of an error code, never null.
+ */
+const char *thimble_error_message(int code);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* THIMBLERIGGER_H */
@@ -0,0 +1,64 @@
+# Run after installing the extension, e.g. `maturin develop --release && python python/test_thimblerigger.py`
+import unittest
+
+import thimblerigger
+
+
+class BoardTest(unittest.TestCase):
+    def test_play(self):
+        board = thimblerigger.Board("445")
+        board.play(5)
+        self.assertEqual(board.moves, "4455")
+        self.assertEqual(board.num_moves, 4)
+        self.assertEqual(repr(board), 'Board("4455")')
+        self.assertIsNone(board.outcome())
+        self.assertEqual(board.display().splitlines()[-2], "...XX..")
+
+    def test_undo(self):
+        board = thimblerigger.Board("4453")
+        self.assertEqual(board.undo(), 3)
+        self.assertEqual(board.moves, "445")
+        self.assertEqual(board.redo(), 3)
+        self.assertIsNone(board.redo())
+        while board.undo() is not None:
+            pass
+        self.assertEqual(board.num_moves, 0)
+
+    def test_outcome(self):
+        self.assertEqual(thimblerigger.Board("1212121").outcome(), "first")
+        self.assertEqual(thimblerigger.Board("12131475").outcome(), "second")
+        self.assertEqual(thimblerigger.Board("1212121").legal_moves(), [])
+
+    def test_invalid(self):
+        with self.assertRaises(ValueError):
+            thimblerigger.Board("448")
+        board = thimblerigger.Board("111111")
+        self.assertFalse(board.can_play(1))
+        with self.assertRaises(ValueError):
+            board.play(1)
+
+
+class SolveTest(unittest.TestCase):
+    def test_solve(self):
+        table = thimblerigger.Table(1 << 16)
+        board = thimblerigger.Board("53233651363321727745271")
+        score, column = thimblerigger.solve(board, table)
+        self.assertEqual(column, 4)
+        scores = thimblerigger.analyze(board, table)
+        self.assertEqual(len(scores), thimblerigger.WIDTH)
+        self.assertEqual(scores[3], score)
+        self.assertTrue(all(s is None or s <= score for s in scores))
+        # the module's own table
+        self.assertEqual(thimblerigger.solve(board), (score, column))
+        self.assertEqual(thimblerigger.analyze(board), scores)
+        with self.assertRaises(ValueError):
+            thimblerigger.Table(0)
+        table.clear()
+
+    def test_finished(self):
+        with self.assertRaises(ValueError):
+            thimblerigger.solve(thimblerigger.Board("1212121"))
+
+
+if __name__ == "__main__":
+    unittest.main()
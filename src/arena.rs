@@ -0,0 +1,266 @@
+use super::board::Board;
+use super::engine::Engine;
+use super::rng::Rng;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Outcome {
+    #[cfg_attr(feature = "serde", serde(rename = "first"))]
+    FirstWins,
+    #[cfg_attr(feature = "serde", serde(rename = "second"))]
+    SecondWins,
+    Draw,
+}
+
+impl Outcome {
+    // the result a finished position implies, None while the game can continue
+    pub fn of(board: &Board) -> Option<Outcome> {
+        if board.last_move_won() {
+            if board.nb_moves() % 2 == 1 {
+                Some(Outcome::FirstWins)
+            } else {
+                Some(Outcome::SecondWins)
+            }
+        } else if board.is_game_over() {
+            Some(Outcome::Draw)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameRecord {
+    // columns as 1 indexed digits, the same format `Board::construct` reads
+    pub moves: String,
+    pub opening_length: usize,
+    pub first: String,
+    pub second: String,
+    pub outcome: Outcome,
+}
+
+// plays out a game from the opening, `first` is the player who moved first from the empty board
+pub fn play_game(opening: &str, first: &mut dyn Engine, second: &mut dyn Engine) -> GameRecord {
+    let mut moves: String = opening.chars().filter(|c| c.is_ascii_digit()).collect();
+    let opening_length = moves.len();
+    first.new_game();
+    second.new_game();
+    let outcome = play_out(Board::construct(opening), &mut moves, |board| {
+        if board.nb_moves() % 2 == 0 {
+            first.best_move(board)
+        } else {
+            second.best_move(board)
+        }
+    });
+    GameRecord {
+        moves,
+        opening_length,
+        first: first.name(),
+        second: second.name(),
+        outcome,
+    }
+}
+
+// the game loop: plays the column `next_move` picks for whoever is to move until the game is
+// over, adding each one to `moves` as a 1 indexed digit
+pub fn play_out<F: FnMut(Board) -> u8>(
+    mut board: Board,
+    moves: &mut String,
+    mut next_move: F,
+) -> Outcome {
+    loop {
+        if let Some(outcome) = Outcome::of(&board) {
+            return outcome;
+        }
+        let action = next_move(board);
+        moves.push((b'1' + action) as char);
+        board.play_col(action);
+    }
+}
+
+pub struct MatchConfig {
+    // each opening is played twice with colours swapped, so this is rounded up to be even
+    pub games: usize,
+    // cut to MAX_OPENING
+    pub opening_length: u8,
+    pub seed: u64,
+}
+
+impl Default for MatchConfig {
+    fn default() -> MatchConfig {
+        MatchConfig {
+            games: 100,
+            opening_length: 4,
+            seed: 0,
+        }
+    }
+}
+
+// results are always from the perspective of the first engine passed to `run_match`
+#[derive(Debug, Default)]
+pub struct MatchResult {
+    pub games: Vec<GameRecord>,
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+}
+
+impl MatchResult {
+    pub fn score(&self) -> f64 {
+        let total = self.wins + self.losses + self.draws;
+        if total == 0 {
+            return 0.5;
+        }
+        (self.wins as f64 + self.draws as f64 / 2.0) / total as f64
+    }
+
+    // Elo difference implied by the score, infinite if one side won every game
+    pub fn elo(&self) -> f64 {
+        let score = self.score();
+        if score <= 0.0 {
+            return f64::NEG_INFINITY;
+        }
+        if score >= 1.0 {
+            return f64::INFINITY;
+        }
+        -400.0 * (1.0 / score - 1.0).log10()
+    }
+
+    // half width of the 95% confidence interval on the Elo difference
+    pub fn elo_margin(&self) -> f64 {
+        let total = (self.wins + self.losses + self.draws) as f64;
+        let score = self.score();
+        if total == 0.0 || score <= 0.0 || score >= 1.0 {
+            return f64::INFINITY;
+        }
+        let win = self.wins as f64 / total;
+        let draw = self.draws as f64 / total;
+        let loss = self.losses as f64 / total;
+        let variance = win * (1.0 - score).powi(2)
+            + draw * (0.5 - score).powi(2)
+            + loss * (0.0 - score).powi(2);
+        let deviation = (variance / total).sqrt();
+        let elo = |s: f64| -400.0 * (1.0 / s - 1.0).log10();
+        let low = (score - 1.96 * deviation).max(f64::MIN_POSITIVE);
+        let high = (score + 1.96 * deviation).min(1.0 - f64::EPSILON);
+        (elo(high) - elo(low)) / 2.0
+    }
+}
+
+// openings are cut to this many moves, longer ones are too often impossible without a four
+pub const MAX_OPENING: u8 = 16;
+
+// random legal opening that doesn't leave an immediate win on the board, at most MAX_OPENING
+// moves long
+pub fn random_opening(rng: &mut Rng, length: u8) -> String {
+    let length = length.min(MAX_OPENING);
+    loop {
+        let mut board = Board::new();
+        let mut moves = String::new();
+        for _ in 0..length {
+            let legal: Vec<u8> = board.legal_columns().collect();
+            let col = legal[rng.below(legal.len() as u32) as usize];
+            if board.is_winning_move(col) {
+                break;
+            }
+            board.play_col(col);
+            moves.push((b'1' + col) as char);
+        }
+        if moves.len() == length as usize && !board.can_win_next() {
+            return moves;
+        }
+    }
+}
+
+pub fn run_match(
+    config: &MatchConfig,
+    engine: &mut dyn Engine,
+    opponent: &mut dyn Engine,
+) -> MatchResult {
+    let mut rng = Rng::new(config.seed);
+    let mut result = MatchResult::default();
+    for _ in 0..config.games.div_ceil(2) {
+        let opening = random_opening(&mut rng, config.opening_length);
+        for engine_first in [true, false].iter() {
+            let game = if *engine_first {
+                play_game(&opening, engine, opponent)
+            } else {
+                play_game(&opening, opponent, engine)
+            };
+            match (game.outcome, *engine_first) {
+                (Outcome::Draw, _) => result.draws += 1,
+                (Outcome::FirstWins, true) | (Outcome::SecondWins, false) => result.wins += 1,
+                _ => result.losses += 1,
+            }
+            result.games.push(game);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{OnePly, RandomPlayer};
+
+    #[test]
+    fn game_ends_with_win() {
+        let game = play_game("445566", &mut OnePly::new(), &mut OnePly::new());
+        assert_eq!(game.opening_length, 6);
+        assert_eq!(game.moves, "4455663");
+        assert_eq!(game.outcome, Outcome::FirstWins);
+
+        // a finished opening isn't played on
+        let mut moves = String::new();
+        let outcome = play_out(Board::construct("1212121"), &mut moves, |_| unreachable!());
+        assert_eq!((outcome, moves.as_str()), (Outcome::FirstWins, ""));
+    }
+
+    #[test]
+    fn openings_are_reproducible() {
+        let mut a = Rng::new(3);
+        let mut b = Rng::new(3);
+        for _ in 0..20 {
+            let opening = random_opening(&mut a, 6);
+            assert_eq!(opening.len(), 6);
+            assert_eq!(opening, random_opening(&mut b, 6));
+        }
+        assert_eq!(random_opening(&mut a, 42).len(), MAX_OPENING as usize);
+    }
+
+    #[test]
+    fn one_ply_beats_random() {
+        let config = MatchConfig {
+            games: 40,
+            opening_length: 2,
+            seed: 1,
+        };
+        let result = run_match(
+            &config,
+            &mut OnePly::randomized(1),
+            &mut RandomPlayer::new(1),
+        );
+        assert_eq!(result.games.len(), 40);
+        assert_eq!(result.wins + result.losses + result.draws, 40);
+        assert!(result.wins > result.losses);
+        assert!(result.elo() > 0.0);
+        assert!(result.elo_margin() > 0.0);
+    }
+
+    #[test]
+    fn elo() {
+        let even = MatchResult {
+            wins: 5,
+            losses: 5,
+            ..MatchResult::default()
+        };
+        assert!(even.elo().abs() < 1e-9);
+        let stronger = MatchResult {
+            wins: 3,
+            losses: 1,
+            ..MatchResult::default()
+        };
+        assert!((stronger.elo() - 190.85).abs() < 0.01);
+    }
+}
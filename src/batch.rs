@@ -0,0 +1,310 @@
+// Solves a stream of positions on a pool of workers, each with its own table, and writes one
+// JSON object per input line in input order:
+//
+//   {"line":1,"moves":"4453","score":-2}
+//   {"line":2,"moves":"448","error":"invalid column at move 3"}
+//
+// Lines are numbered from 1 and only the first word of each is read, so test case files with
+// expected scores work as they are. Blank lines get an error rather than the empty board.
+// Progress is saved to a checkpoint so an interrupted run can pick up where it stopped.
+
+use super::board::Board;
+use super::solver::solve;
+use super::table::Table;
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchConfig {
+    pub workers: usize,
+    // entries per worker table
+    pub table_capacity: u64,
+    // lines written between checkpoints
+    pub checkpoint_every: u64,
+}
+
+impl Default for BatchConfig {
+    fn default() -> BatchConfig {
+        BatchConfig {
+            workers: thread::available_parallelism().map_or(1, |n| n.get()),
+            table_capacity: 1_048_573,
+            checkpoint_every: 1000,
+        }
+    }
+}
+
+// how much of the output is complete
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub lines: u64,
+    pub bytes: u64,
+}
+
+impl Checkpoint {
+    pub fn load(path: &Path) -> io::Result<Option<Checkpoint>> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error),
+        };
+        let mut numbers = text.split_whitespace().map(|n| n.parse::<u64>());
+        match (numbers.next(), numbers.next()) {
+            (Some(Ok(lines)), Some(Ok(bytes))) => Ok(Some(Checkpoint { lines, bytes })),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "malformed checkpoint",
+            )),
+        }
+    }
+
+    // written to a temporary file first so a crash can't leave half a checkpoint
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+        fs::write(&temporary, format!("{} {}\n", self.lines, self.bytes))?;
+        fs::rename(&temporary, path)
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn solve_line(number: u64, line: &str, table: &mut Table) -> String {
+    let moves = line.split_whitespace().next().unwrap_or("");
+    let result = match Board::parse(moves) {
+        // most likely a stray blank line rather than a request to solve the empty board
+        _ if moves.is_empty() => format!("\"error\":{}", json_string("empty line")),
+        Ok(position) if position.is_game_over() => {
+            format!("\"error\":{}", json_string("game is already over"))
+        }
+        Ok(position) => format!("\"score\":{}", solve(position, table).0),
+        Err(error) => format!("\"error\":{}", json_string(&error.to_string())),
+    };
+    format!(
+        "{{\"line\":{},\"moves\":{},{}}}",
+        number,
+        json_string(moves),
+        result
+    )
+}
+
+// solves every line of `input` after the `start.lines` already done, calling `save` with
+// the progress every `config.checkpoint_every` lines and once at the end
+pub fn solve_lines<R, W, S>(
+    input: R,
+    mut output: W,
+    config: &BatchConfig,
+    start: Checkpoint,
+    mut save: S,
+) -> io::Result<Checkpoint>
+where
+    R: BufRead + Send,
+    W: Write,
+    S: FnMut(&Checkpoint) -> io::Result<()>,
+{
+    let workers = config.workers.max(1);
+    let every = config.checkpoint_every.max(1);
+    let (job_sender, jobs) = mpsc::sync_channel::<(u64, String)>(workers * 4);
+    let jobs = Arc::new(Mutex::new(jobs));
+    let (result_sender, results) = mpsc::channel::<(u64, String)>();
+    thread::scope(|scope| {
+        let reader = scope.spawn(move || -> io::Result<()> {
+            for (index, line) in input.lines().enumerate().skip(start.lines as usize) {
+                // the receivers only go away if writing failed, which is reported instead
+                if job_sender.send((index as u64, line?)).is_err() {
+                    break;
+                }
+            }
+            Ok(())
+        });
+        for _ in 0..workers {
+            let jobs = jobs.clone();
+            let results = result_sender.clone();
+            let capacity = config.table_capacity;
+            scope.spawn(move || {
+                let mut table = Table::with_capacity(capacity);
+                loop {
+                    let job = jobs.lock().unwrap().recv();
+                    let (index, line) = match job {
+                        Ok(job) => job,
+                        Err(_) => break,
+                    };
+                    if results
+                        .send((index, solve_line(index + 1, &line, &mut table)))
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(result_sender);
+
+        // results arrive in any order, hold them back until the ones before are written
+        let mut progress = start;
+        let mut pending = BTreeMap::new();
+        let mut written = Ok(());
+        for (index, json) in results.iter() {
+            pending.insert(index, json);
+            while let Some(json) = pending.remove(&progress.lines) {
+                written = written.and_then(|_| writeln!(output, "{}", json));
+                progress.lines += 1;
+                progress.bytes += json.len() as u64 + 1;
+                if written.is_ok() && progress.lines % every == 0 {
+                    written = output.flush().and_then(|_| save(&progress));
+                }
+            }
+            if written.is_err() {
+                break;
+            }
+        }
+        // dropping the results makes the workers and then the reader stop early on errors
+        drop(results);
+        written?;
+        reader.join().unwrap()?;
+        output.flush()?;
+        save(&progress)?;
+        Ok(progress)
+    })
+}
+
+pub fn checkpoint_path(output: &Path) -> PathBuf {
+    let mut path = output.as_os_str().to_owned();
+    path.push(".checkpoint");
+    PathBuf::from(path)
+}
+
+// solves `input` (a file or "-" for stdin) into the `output` file. If a checkpoint from an
+// interrupted run exists the output is cut back to it and the run continues from there.
+// The checkpoint is removed once every line is done.
+pub fn solve_file(input: &str, output: &Path, config: &BatchConfig) -> io::Result<Checkpoint> {
+    let checkpoint = checkpoint_path(output);
+    let start = Checkpoint::load(&checkpoint)?.unwrap_or_default();
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(output)?;
+    file.set_len(start.bytes)?;
+    let mut writer = BufWriter::new(file);
+    io::Seek::seek(&mut writer, io::SeekFrom::End(0))?;
+    let save = |progress: &Checkpoint| progress.save(&checkpoint);
+    let done = if input == "-" {
+        solve_lines(BufReader::new(io::stdin()), writer, config, start, save)?
+    } else {
+        let input = BufReader::new(File::open(input)?);
+        solve_lines(input, writer, config, start, save)?
+    };
+    fs::remove_file(&checkpoint)?;
+    Ok(done)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const INPUT: &str = "73463244621316374157434 10\n\
+                         448\n\
+                         \n\
+                         53233651363321727745271\n\
+                         1212121\n\
+                         257771314744647214174561\n";
+
+    fn config(workers: usize) -> BatchConfig {
+        BatchConfig {
+            workers,
+            table_capacity: 65521,
+            checkpoint_every: 2,
+        }
+    }
+
+    #[test]
+    fn input_order() {
+        let mut output = Vec::new();
+        let mut saved = Vec::new();
+        let done = solve_lines(
+            Cursor::new(INPUT),
+            &mut output,
+            &config(3),
+            Checkpoint::default(),
+            |progress| {
+                saved.push(*progress);
+                Ok(())
+            },
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(
+            lines[0],
+            r#"{"line":1,"moves":"73463244621316374157434","score":10}"#
+        );
+        assert_eq!(
+            lines[1],
+            r#"{"line":2,"moves":"448","error":"invalid column at move 3"}"#
+        );
+        assert_eq!(lines[2], r#"{"line":3,"moves":"","error":"empty line"}"#);
+        assert_eq!(
+            lines[4],
+            r#"{"line":5,"moves":"1212121","error":"game is already over"}"#
+        );
+        assert!(lines[5].ends_with(r#""score":-2}"#));
+        assert_eq!(done.lines, 6);
+        assert_eq!(done.bytes, output.len() as u64);
+        assert_eq!(saved.last(), Some(&done));
+        assert!(saved.iter().any(|progress| progress.lines == 2));
+    }
+
+    #[test]
+    fn resume() {
+        let directory = std::env::temp_dir().join(format!("thimble-batch-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let input = directory.join("positions.txt");
+        let output = directory.join("scores.jsonl");
+        fs::write(&input, INPUT.replace("\n\n", "\n")).unwrap();
+
+        // an interrupted run: two complete lines, a third half written
+        let mut expected = Vec::new();
+        solve_lines(
+            Cursor::new(INPUT.replace("\n\n", "\n")),
+            &mut expected,
+            &config(1),
+            Checkpoint::default(),
+            |_| Ok(()),
+        )
+        .unwrap();
+        let expected = String::from_utf8(expected).unwrap();
+        let bytes: usize = expected.lines().take(2).map(|line| line.len() + 1).sum();
+        fs::write(&output, &expected[..bytes + 5]).unwrap();
+        Checkpoint {
+            lines: 2,
+            bytes: bytes as u64,
+        }
+        .save(&checkpoint_path(&output))
+        .unwrap();
+
+        let done = solve_file(input.to_str().unwrap(), &output, &config(2)).unwrap();
+        assert_eq!(done.lines, 5);
+        assert_eq!(fs::read_to_string(&output).unwrap(), expected);
+        assert!(!checkpoint_path(&output).exists());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
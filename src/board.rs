@@ -1,4 +1,71 @@
-#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+use std::fmt;
+
+// why a move string was rejected, with the index of the offending character
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MoveError {
+    InvalidColumn(usize),
+    ColumnFull(usize),
+    GameOver(usize),
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::InvalidColumn(index) => write!(f, "invalid column at move {}", index + 1),
+            MoveError::ColumnFull(index) => write!(f, "column is full at move {}", index + 1),
+            MoveError::GameOver(index) => write!(f, "game is already over at move {}", index + 1),
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+// why a grid diagram doesn't describe a reachable position. Rows count from the bottom.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GridError {
+    InvalidCharacter(char),
+    TooManyRows,
+    RowTooLong(u8),
+    FloatingStone { col: u8, row: u8 },
+    StoneCount { x: u8, o: u8 },
+    TwoWinners,
+    // the player to move already has four in a row
+    WinnerToMove,
+    // bits set outside the 7x6 board, only possible in raw bitboards
+    OffBoard,
+    // the move count doesn't match the stones, only possible in raw bitboards
+    MoveCount { moves: u8, stones: u8 },
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GridError::InvalidCharacter(c) => write!(f, "invalid character {:?}", c),
+            GridError::TooManyRows => write!(f, "more than {} rows", Board::HEIGHT),
+            GridError::RowTooLong(row) => {
+                write!(f, "row {} has more than {} cells", row + 1, Board::WIDTH)
+            }
+            GridError::FloatingStone { col, row } => {
+                write!(f, "stone at column {} row {} is floating", col + 1, row + 1)
+            }
+            GridError::StoneCount { x, o } => {
+                write!(f, "{} X stones and {} O stones can't happen", x, o)
+            }
+            GridError::TwoWinners => write!(f, "both players have four in a row"),
+            GridError::WinnerToMove => write!(f, "the player to move has already won"),
+            GridError::OffBoard => write!(f, "stones outside the board"),
+            GridError::MoveCount { moves, stones } => {
+                write!(f, "{} moves can't leave {} stones", moves, stones)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GridError {}
+
+#[derive(Hash, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RawBoard"))]
 pub struct Board {
     // indexed by col, row
     stones_all: u64,
@@ -6,11 +73,55 @@ pub struct Board {
     num_moves: u8,
 }
 
+// the serialized fields of a Board, which have to describe a reachable position
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RawBoard {
+    stones_all: u64,
+    stones_player: u64,
+    num_moves: u8,
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<RawBoard> for Board {
+    type Error = GridError;
+
+    fn try_from(raw: RawBoard) -> Result<Board, GridError> {
+        if raw.stones_all & !Board::board_mask() != 0 || raw.stones_player & !raw.stones_all != 0 {
+            return Err(GridError::OffBoard);
+        }
+        let opponent = raw.stones_all ^ raw.stones_player;
+        let (x_stones, o_stones) = if raw.num_moves % 2 == 0 {
+            (raw.stones_player, opponent)
+        } else {
+            (opponent, raw.stones_player)
+        };
+        let board = Board::from_stones(x_stones, o_stones)?;
+        if board.stones_all != raw.stones_all
+            || board.stones_player != raw.stones_player
+            || board.num_moves != raw.num_moves
+        {
+            return Err(GridError::MoveCount {
+                moves: raw.num_moves,
+                stones: raw.stones_all.count_ones() as u8,
+            });
+        }
+        Ok(board)
+    }
+}
+
+impl Default for Board {
+    fn default() -> Board {
+        Board::new()
+    }
+}
+
 impl Board {
     pub const WIDTH: u8 = 7;
     pub const HEIGHT: u8 = 6;
     pub const MAX_SCORE: i32 = ((Board::WIDTH * Board::HEIGHT + 1) as i32) / 2 - 3;
     pub const MIN_SCORE: i32 = -((Board::WIDTH * Board::HEIGHT) as i32) / 2 + 3;
+    #[allow(non_upper_case_globals)]
     pub const exploration_order: [u8; 7] = [3, 2, 4, 1, 5, 0, 6];
 
     pub fn new() -> Board {
@@ -21,18 +132,123 @@ impl Board {
         }
     }
 
+    // characters other than columns 1 to 7 and moves into full columns are skipped. Play carries
+    // on after a four, use parse to reject any of that instead.
     pub fn construct(instructions: &str) -> Board {
         let mut board = Board::new();
         for play in instructions.chars() {
-            if !play.is_ascii_digit() {
-                continue;
+            let col = match play.to_digit(10) {
+                Some(col) if col >= 1 && col <= Board::WIDTH as u32 => (col - 1) as u8,
+                _ => continue,
+            };
+            if board.can_play(col) {
+                board.play_col(col);
             }
-            let col = play.to_digit(10).unwrap() as u8;
-            board.play_col(col - 1);
         }
         board
     }
 
+    // like construct, but rejects anything that isn't a legal sequence of moves.
+    // Whitespace is ignored and a game may end on a winning move.
+    pub fn parse(instructions: &str) -> Result<Board, MoveError> {
+        let mut board = Board::new();
+        let mut game_over = false;
+        for (index, play) in instructions
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .enumerate()
+        {
+            let col = match play.to_digit(10) {
+                Some(col) if col >= 1 && col <= Board::WIDTH as u32 => (col - 1) as u8,
+                _ => return Err(MoveError::InvalidColumn(index)),
+            };
+            if game_over {
+                return Err(MoveError::GameOver(index));
+            }
+            if !board.can_play(col) {
+                return Err(MoveError::ColumnFull(index));
+            }
+            game_over = board.is_winning_move(col);
+            board.play_col(col);
+        }
+        Ok(board)
+    }
+
+    // builds a position from a diagram of X, O and empty cells ('.' or ' '), top row first.
+    // Borders made of '-', column numbers and missing rows at the top are ignored, so the
+    // output of `display` reads back. Whoever has more stones moved first, X when level.
+    pub fn from_grid(grid: &str) -> Result<Board, GridError> {
+        let rows: Vec<&str> = grid
+            .lines()
+            .map(|line| line.trim_end_matches('\r'))
+            .filter(|line| {
+                !line.is_empty()
+                    && !line.chars().all(|c| c == '-')
+                    && !line.chars().all(|c| c.is_ascii_digit())
+            })
+            .collect();
+        if rows.len() > Board::HEIGHT as usize {
+            return Err(GridError::TooManyRows);
+        }
+        let mut x_stones = 0_u64;
+        let mut o_stones = 0_u64;
+        for (index, line) in rows.iter().rev().enumerate() {
+            let row = index as u8;
+            let cells = line.trim_end();
+            if cells.chars().count() > Board::WIDTH as usize {
+                return Err(GridError::RowTooLong(row));
+            }
+            for (col, cell) in cells.chars().enumerate() {
+                let stone = 1_u64 << (col as u8 * (Board::HEIGHT + 1) + row);
+                match cell {
+                    'X' | 'x' => x_stones |= stone,
+                    'O' | 'o' => o_stones |= stone,
+                    '.' | ' ' => {}
+                    _ => return Err(GridError::InvalidCharacter(cell)),
+                }
+            }
+        }
+        Board::from_stones(x_stones, o_stones)
+    }
+
+    // checks the stones of each player make a position some game could reach, whoever has more
+    // stones moved first
+    fn from_stones(x_stones: u64, o_stones: u64) -> Result<Board, GridError> {
+        let stones_all = x_stones | o_stones;
+        // every stone needs a stone (or the floor) beneath it
+        let floating = stones_all & !((stones_all << 1) | Board::bottom_mask());
+        if floating != 0 {
+            let index = floating.trailing_zeros() as u8;
+            return Err(GridError::FloatingStone {
+                col: index / (Board::HEIGHT + 1),
+                row: index % (Board::HEIGHT + 1),
+            });
+        }
+        let x = x_stones.count_ones() as u8;
+        let o = o_stones.count_ones() as u8;
+        let (first, second) = if x >= o {
+            (x_stones, o_stones)
+        } else {
+            (o_stones, x_stones)
+        };
+        if x.max(o) - x.min(o) > 1 {
+            return Err(GridError::StoneCount { x, o });
+        }
+        // the second player is to move when the first is a stone ahead
+        let stones_player = if x == o { first } else { second };
+        if Board::has_four(first) && Board::has_four(second) {
+            return Err(GridError::TwoWinners);
+        }
+        if Board::has_four(stones_player) {
+            return Err(GridError::WinnerToMove);
+        }
+        Ok(Board {
+            stones_all,
+            stones_player,
+            num_moves: x + o,
+        })
+    }
+
     pub fn hash(&self) -> u64 {
         self.stones_all + self.stones_player
     }
@@ -44,12 +260,13 @@ impl Board {
         })
     }
 
-    fn board_mask() -> u64 {
+    // every square of the board, without the spare bit on top of each column
+    pub fn board_mask() -> u64 {
         ((1_u64 << Board::HEIGHT) - 1) * Board::bottom_mask()
     }
 
-    fn can_play(&self, col: u8) -> bool {
-        self.stones_all & Board::top_mask(col) == 0
+    pub fn can_play(&self, col: u8) -> bool {
+        col < Board::WIDTH && self.stones_all & Board::top_mask(col) == 0
     }
 
     fn play(&mut self, move_mask: u64) {
@@ -58,18 +275,67 @@ impl Board {
         self.num_moves += 1;
     }
 
+    // panics if the column is full or off the board, check with `can_play` first
     pub fn play_col(&mut self, col: u8) {
+        assert!(self.can_play(col), "column {} can't be played", col);
         self.play((self.stones_all + Board::bottom_mask_col(col)) & Board::col_mask(col));
     }
 
+    // for the solver's inner loop, which already knows the move is legal: `moves` only has
+    // playable squares, like `possible()` or `nonlosing_moves()`
+    pub(crate) fn play_from(&mut self, moves: u64, col: u8) {
+        debug_assert!(moves & Board::col_mask(col) & self.possible() != 0);
+        self.play(moves & Board::col_mask(col));
+    }
+
     pub fn can_win_next(&self) -> bool {
         self.winning_moves() & self.possible() != 0
     }
 
-    fn possible(&self) -> u64 {
+    // columns set in a move mask like `possible()` or `nonlosing_moves()`, in exploration order
+    pub fn columns(moves: u64) -> impl DoubleEndedIterator<Item = u8> {
+        Board::exploration_order
+            .iter()
+            .copied()
+            .filter(move |&col| moves & Board::col_mask(col) != 0)
+    }
+
+    // playable columns in exploration order, none once the game is over
+    pub fn legal_columns(&self) -> impl DoubleEndedIterator<Item = u8> {
+        let moves = if self.is_game_over() {
+            0
+        } else {
+            self.possible()
+        };
+        Board::columns(moves)
+    }
+
+    pub fn possible(&self) -> u64 {
         (self.stones_all + Board::bottom_mask()) & Board::board_mask()
     }
 
+    // true when the player who made the last move has four in a row
+    pub fn last_move_won(&self) -> bool {
+        Board::has_four(self.stones_player ^ self.stones_all)
+    }
+
+    fn has_four(stones: u64) -> bool {
+        [1, Board::HEIGHT, Board::HEIGHT + 1, Board::HEIGHT + 2]
+            .iter()
+            .any(|&shift| {
+                let pairs = stones & (stones >> shift);
+                pairs & (pairs >> (2 * shift)) != 0
+            })
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.num_moves == Board::WIDTH * Board::HEIGHT || self.last_move_won()
+    }
+
+    pub fn is_winning_move(&self, col: u8) -> bool {
+        self.winning_moves() & self.possible() & Board::col_mask(col) != 0
+    }
+
     pub fn winning_move(&self) -> u8 {
         let possible = self.possible();
         let winning = self.winning_moves();
@@ -214,40 +480,107 @@ impl Board {
         ((1_u64 << Board::HEIGHT) - 1) << (col * (Board::HEIGHT + 1))
     }
 
+    // row 0 is the bottom
+    pub fn square_mask(col: u8, row: u8) -> u64 {
+        1 << (col * (Board::HEIGHT + 1) + row)
+    }
+
     fn bottom_mask_col(col: u8) -> u64 {
         1_u64 << (col * (Board::HEIGHT + 1))
     }
 
-    fn copy(&self) -> Board {
+    fn accessor(target: u64, row: u8, col: u8) -> bool {
+        let index = (col * (Board::HEIGHT + 1) + row) as u64;
+        (target & (1_u64 << index)) > 0
+    }
+
+    // 0 for a stone of the first player, 1 for the second, None when the cell is empty
+    pub fn owner(&self, col: u8, row: u8) -> Option<u8> {
+        if !Board::accessor(self.stones_all, row, col) {
+            return None;
+        }
+        let to_move = Board::accessor(self.stones_player, row, col);
+        let first_to_move = self.num_moves % 2 == 0;
+        if to_move == first_to_move {
+            Some(0)
+        } else {
+            Some(1)
+        }
+    }
+
+    // the stones of the first (0) or second (1) player
+    pub fn stones(&self, player: u8) -> u64 {
+        let to_move = self.num_moves % 2 == player % 2;
+        if to_move {
+            self.stones_player
+        } else {
+            self.stones_player ^ self.stones_all
+        }
+    }
+
+    // every stone that is part of a line of four
+    pub fn fours(stones: u64) -> u64 {
+        let mut result = 0;
+        for &shift in [1, Board::HEIGHT, Board::HEIGHT + 1, Board::HEIGHT + 2].iter() {
+            let pairs = stones & (stones >> shift);
+            let starts = pairs & (pairs >> (2 * shift));
+            result |= starts | starts << shift | starts << (2 * shift) | starts << (3 * shift);
+        }
+        result
+    }
+
+    // the same position flipped left to right
+    pub fn mirror(&self) -> Board {
+        let stride = Board::HEIGHT + 1;
+        let flip = |stones: u64| {
+            (0..Board::WIDTH).fold(0_u64, |accum, col| {
+                let column = (stones >> (col * stride)) & ((1 << stride) - 1);
+                accum | column << ((Board::WIDTH - 1 - col) * stride)
+            })
+        };
         Board {
-            stones_all: self.stones_all,
-            stones_player: self.stones_player,
+            stones_all: flip(self.stones_all),
+            stones_player: flip(self.stones_player),
             num_moves: self.num_moves,
         }
     }
 
-    fn accessor(target: u64, row: u8, col: u8) -> bool {
-        let index = (col * (Board::HEIGHT + 1) + row) as u64;
-        (target & (1_u64 << index)) > 0
+    pub fn display(&self) {
+        print!("{}", self);
     }
+}
 
-    pub fn display(&self) {
-        println!("-------");
+// X for the first player and O for the second whoever is to move, with column numbers
+// underneath. `Board::from_grid` reads it back.
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for row in (0..Board::HEIGHT).rev() {
             for col in 0..Board::WIDTH {
-                if Board::accessor(self.stones_all, row, col) {
-                    if Board::accessor(self.stones_player, row, col) {
-                        print!("X");
-                    } else {
-                        print!("O");
-                    }
-                } else {
-                    print!(" ");
-                }
+                let cell = match self.owner(col, row) {
+                    Some(0) => 'X',
+                    Some(_) => 'O',
+                    None => '.',
+                };
+                write!(f, "{}", cell)?;
             }
-            println!();
+            writeln!(f)?;
         }
-        println!("-------");
+        for col in 0..Board::WIDTH {
+            write!(f, "{}", col + 1)?;
+        }
+        writeln!(f)
+    }
+}
+
+// the rows of the display so failed assertions show the position, `{:#?}` puts one per line
+impl fmt::Debug for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let display = self.to_string();
+        let rows: Vec<&str> = display.lines().take(Board::HEIGHT as usize).collect();
+        f.debug_struct("Board")
+            .field("moves", &self.num_moves)
+            .field("rows", &rows)
+            .finish()
     }
 }
 
@@ -255,6 +588,13 @@ impl Board {
 mod tests {
     use super::*;
 
+    #[test]
+    #[should_panic(expected = "column 0 can't be played")]
+    fn full_column() {
+        let mut board = Board::construct("111111");
+        board.play_col(0);
+    }
+
     #[test]
     fn starting_board() {
         let board = Board::new();
@@ -318,6 +658,153 @@ mod tests {
         assert!(board.can_win_next());
     }
 
+    #[test]
+    fn owner() {
+        let board = Board::construct("4451");
+        assert_eq!(board.owner(3, 0), Some(0));
+        assert_eq!(board.owner(3, 1), Some(1));
+        assert_eq!(board.owner(4, 0), Some(0));
+        assert_eq!(board.owner(0, 0), Some(1));
+        assert_eq!(board.owner(0, 1), None);
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(Board::parse("4453 32"), Ok(Board::construct("445332")));
+        assert_eq!(Board::parse("448"), Err(MoveError::InvalidColumn(2)));
+        assert_eq!(Board::parse("40"), Err(MoveError::InvalidColumn(1)));
+        assert_eq!(Board::parse("1111111"), Err(MoveError::ColumnFull(6)));
+        assert!(Board::parse("1212121").is_ok());
+        // construct skips what parse rejects
+        assert_eq!(
+            Board::construct("40891111111"),
+            Board::parse("4111111").unwrap()
+        );
+        assert!(!Board::new().can_play(Board::WIDTH));
+        assert_eq!(Board::parse("12121212"), Err(MoveError::GameOver(7)));
+    }
+
+    #[test]
+    fn game_over() {
+        assert!(!Board::construct("121212").is_game_over());
+        assert!(Board::construct("1212121").last_move_won());
+        assert!(Board::construct("4455667").last_move_won());
+        assert!(Board::construct("12233434454").last_move_won());
+        assert!(!Board::construct("12233434455").last_move_won());
+    }
+
+    #[test]
+    fn from_grid() {
+        let board = Board::from_grid(
+            ".......
+             .......",
+        );
+        assert_eq!(board, Err(GridError::RowTooLong(0)));
+
+        let grid = "\
+.......
+.......
+.......
+.......
+..OO...
+..XX...";
+        assert_eq!(Board::from_grid(grid), Ok(Board::construct("4433")));
+        assert_eq!(
+            Board::from_grid("...O...\n..XXX.."),
+            Err(GridError::StoneCount { x: 3, o: 1 })
+        );
+        assert_eq!(
+            Board::from_grid("...X...\n..OX..."),
+            Ok(Board::construct("434"))
+        );
+        assert_eq!(Board::from_grid(""), Ok(Board::new()));
+        assert_eq!(
+            Board::from_grid("...X...\n.......\n...O..."),
+            Err(GridError::FloatingStone { col: 3, row: 2 })
+        );
+        assert_eq!(
+            Board::from_grid("..Z...."),
+            Err(GridError::InvalidCharacter('Z'))
+        );
+        assert_eq!(
+            Board::from_grid("X......\nX......\nX..O...\nX..OOO."),
+            Err(GridError::WinnerToMove)
+        );
+        assert_eq!(
+            Board::from_grid("O......\nO......\nO......\nOXXXX.."),
+            Err(GridError::TwoWinners)
+        );
+        // the last player to move may have won
+        assert_eq!(
+            Board::from_grid("O......\nO......\nOXXXX.."),
+            Ok(Board::construct("2131415"))
+        );
+    }
+
+    #[test]
+    fn grid_round_trip() {
+        for moves in [
+            "",
+            "4",
+            "4433",
+            "1213141",
+            "444345443332565356",
+            "257771314744647214174561",
+        ]
+        .iter()
+        {
+            let board = Board::construct(moves);
+            assert_eq!(Board::from_grid(&board.to_string()), Ok(board), "{}", moves);
+        }
+    }
+
+    #[test]
+    fn display() {
+        // symbols stay with the player rather than the side to move
+        assert_eq!(
+            Board::construct("4453").to_string(),
+            ".......\n.......\n.......\n.......\n...O...\n..OXX..\n1234567\n"
+        );
+        assert!(Board::construct("44533")
+            .to_string()
+            .ends_with("..XO...\n..OXX..\n1234567\n"));
+        // the old bordered output still reads back
+        let grid = "-------\n       \n       \n       \n       \n   O   \n  OXX  \n-------\n";
+        assert_eq!(Board::from_grid(grid), Ok(Board::construct("4453")));
+        assert!(format!("{:?}", Board::construct("4453")).contains("\"..OXX..\"]"));
+    }
+
+    #[test]
+    fn fours() {
+        let board = Board::construct("1122334");
+        assert_eq!(board.stones(0).count_ones(), 4);
+        assert_eq!(board.stones(1).count_ones(), 3);
+        assert_eq!(Board::fours(board.stones(0)), board.stones(0));
+        assert_eq!(board.stones(0) & Board::bottom_mask(), board.stones(0));
+        assert_eq!(Board::fours(board.stones(1)), 0);
+    }
+
+    #[test]
+    fn columns() {
+        let columns: Vec<u8> = Board::new().legal_columns().collect();
+        assert_eq!(columns, Board::exploration_order.to_vec());
+        let board = Board::construct("444444");
+        let columns: Vec<u8> = board.legal_columns().rev().collect();
+        assert_eq!(columns, vec![6, 0, 5, 1, 4, 2]);
+        assert_eq!(Board::construct("1212121").legal_columns().count(), 0);
+        let mask = Board::col_mask(0) | Board::col_mask(4);
+        assert_eq!(Board::columns(mask).collect::<Vec<u8>>(), vec![4, 0]);
+    }
+
+    #[test]
+    fn mirror() {
+        assert_eq!(Board::construct("1123").mirror(), Board::construct("7765"));
+        let board = Board::construct("4453");
+        assert_eq!(board.mirror(), Board::construct("4435"));
+        assert_eq!(board.mirror().mirror(), board);
+        assert_eq!(Board::construct("44").mirror(), Board::construct("44"));
+    }
+
     #[test]
     fn opponent_win() {
         let board_org = Board::construct("13141");
@@ -333,4 +820,28 @@ mod tests {
             Board::bottom_mask()
         );
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let board = Board::construct("4453");
+        let json = serde_json::to_string(&board).unwrap();
+        assert_eq!(serde_json::from_str::<Board>(&json).unwrap(), board);
+        // a stone in the sentinel row of the first column, one floating in the air, a player
+        // with one stone too many and a move count that disagrees with the stones
+        for &(all, player, moves) in [
+            (0b1111111, 0b0101010, 7),
+            (1 << 8, 0, 1),
+            (0b111, 0b000, 3),
+            (0b11, 0b00, 3),
+        ]
+        .iter()
+        {
+            let json = format!(
+                "{{\"stones_all\":{},\"stones_player\":{},\"num_moves\":{}}}",
+                all, player, moves
+            );
+            assert!(serde_json::from_str::<Board>(&json).is_err(), "{}", json);
+        }
+    }
 }
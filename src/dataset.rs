@@ -0,0 +1,257 @@
+// The format of the UCI Connect-4 data set: 42 comma separated cells, x for the first player,
+// o for the second and b for blank, going up each column from a1 to g6, then the result for
+// the first player (win, loss or draw) with perfect play. The empty board would be:
+//
+//   b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,win
+//
+// The published set has every 8 ply position where neither player has won or can win next.
+
+use super::board::{Board, GridError};
+use super::solver::{solve, WeakScore};
+use super::table::Table;
+use std::fmt;
+use std::io::{self, BufRead};
+
+const CELLS: usize = (Board::WIDTH * Board::HEIGHT) as usize;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DatasetError {
+    // how many fields the row had
+    FieldCount(usize),
+    InvalidCell(String),
+    InvalidOutcome(String),
+    Position(GridError),
+    // a four or a full board, which has nothing left to solve
+    GameOver,
+}
+
+impl fmt::Display for DatasetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DatasetError::FieldCount(count) => {
+                write!(f, "expected {} fields but found {}", CELLS + 1, count)
+            }
+            DatasetError::InvalidCell(cell) => write!(f, "invalid cell {:?}", cell),
+            DatasetError::InvalidOutcome(outcome) => write!(f, "invalid outcome {:?}", outcome),
+            DatasetError::Position(error) => write!(f, "{}", error),
+            DatasetError::GameOver => write!(f, "the game is already over"),
+        }
+    }
+}
+
+impl std::error::Error for DatasetError {}
+
+fn outcome_name(outcome: WeakScore) -> &'static str {
+    match outcome {
+        WeakScore::Win => "win",
+        WeakScore::Loss => "loss",
+        WeakScore::Draw => "draw",
+    }
+}
+
+// the position and the result for the first player
+pub fn parse_row(row: &str) -> Result<(Board, WeakScore), DatasetError> {
+    let fields: Vec<&str> = row.trim().split(',').map(|field| field.trim()).collect();
+    if fields.len() != CELLS + 1 {
+        return Err(DatasetError::FieldCount(fields.len()));
+    }
+    let mut grid = String::new();
+    let (mut x, mut o) = (0, 0);
+    for row in (0..Board::HEIGHT as usize).rev() {
+        for col in 0..Board::WIDTH as usize {
+            let cell = fields[col * Board::HEIGHT as usize + row];
+            grid.push(match cell {
+                "x" => {
+                    x += 1;
+                    'X'
+                }
+                "o" => {
+                    o += 1;
+                    'O'
+                }
+                "b" => '.',
+                _ => return Err(DatasetError::InvalidCell(cell.to_string())),
+            });
+        }
+        grid.push('\n');
+    }
+    // from_grid lets whoever is ahead be the first player, here it's always x
+    if o > x {
+        return Err(DatasetError::Position(GridError::StoneCount { x, o }));
+    }
+    let board = Board::from_grid(&grid).map_err(DatasetError::Position)?;
+    let outcome = match fields[CELLS] {
+        "win" => WeakScore::Win,
+        "loss" => WeakScore::Loss,
+        "draw" => WeakScore::Draw,
+        outcome => return Err(DatasetError::InvalidOutcome(outcome.to_string())),
+    };
+    Ok((board, outcome))
+}
+
+pub fn format_row(board: &Board, outcome: WeakScore) -> String {
+    let mut row = String::new();
+    for col in 0..Board::WIDTH {
+        for r in 0..Board::HEIGHT {
+            row.push_str(match board.owner(col, r) {
+                Some(0) => "x,",
+                Some(_) => "o,",
+                None => "b,",
+            });
+        }
+    }
+    row.push_str(outcome_name(outcome));
+    row
+}
+
+// the result for the first player, finished positions are left to whoever finished them
+pub fn label(board: Board, table: &mut Table) -> Result<WeakScore, DatasetError> {
+    if board.is_game_over() {
+        return Err(DatasetError::GameOver);
+    }
+    let outcome = WeakScore::of(solve(board, table).0);
+    if board.nb_moves() % 2 == 0 {
+        Ok(outcome)
+    } else {
+        Ok(outcome.flip())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Disagreement {
+    // 1 indexed
+    pub line: usize,
+    pub board: Board,
+    pub expected: WeakScore,
+    pub solved: WeakScore,
+}
+
+impl fmt::Display for Disagreement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}: expected {} but solved {}",
+            self.line,
+            outcome_name(self.expected),
+            outcome_name(self.solved)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Verification {
+    pub rows: usize,
+    pub disagreements: Vec<Disagreement>,
+    // line numbers of rows that couldn't be read, 1 indexed
+    pub errors: Vec<(usize, DatasetError)>,
+}
+
+// solves every row of a data set file and compares with its outcome. Blank lines are skipped,
+// finished positions count as errors.
+pub fn verify<R: BufRead>(input: R, table: &mut Table) -> io::Result<Verification> {
+    let mut verification = Verification::default();
+    for (index, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        verification.rows += 1;
+        let row = parse_row(&line).and_then(|(board, expected)| {
+            label(board, table).map(|solved| (board, expected, solved))
+        });
+        let (board, expected, solved) = match row {
+            Ok(row) => row,
+            Err(error) => {
+                verification.errors.push((index + 1, error));
+                continue;
+            }
+        };
+        if solved != expected {
+            verification.disagreements.push(Disagreement {
+                line: index + 1,
+                board,
+                expected,
+                solved,
+            });
+        }
+    }
+    Ok(verification)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn round_trip() {
+        let board = Board::construct("44322");
+        let row = format_row(&board, WeakScore::Win);
+        assert_eq!(
+            row,
+            "b,b,b,b,b,b,o,x,b,b,b,b,x,b,b,b,b,b,x,o,b,b,b,b,\
+             b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,win"
+        );
+        assert_eq!(parse_row(&row), Ok((board, WeakScore::Win)));
+        assert_eq!(
+            parse_row(&format_row(&Board::new(), WeakScore::Draw)),
+            Ok((Board::new(), WeakScore::Draw))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse_row("b,b,win"), Err(DatasetError::FieldCount(3)));
+        let empty = "b,".repeat(CELLS);
+        assert_eq!(
+            parse_row(&format!("{}lose", empty)),
+            Err(DatasetError::InvalidOutcome("lose".to_string()))
+        );
+        let mut cells = empty;
+        cells.replace_range(0..1, "q");
+        assert_eq!(
+            parse_row(&format!("{}draw", cells)),
+            Err(DatasetError::InvalidCell("q".to_string()))
+        );
+        cells.replace_range(0..1, "o");
+        assert_eq!(
+            parse_row(&format!("{}draw", cells)),
+            Err(DatasetError::Position(GridError::StoneCount { x: 0, o: 1 }))
+        );
+    }
+
+    #[test]
+    fn verify_rows() {
+        let mut table = Table::with_capacity(1 << 20);
+        // the second player wins, and all moves lose for the first player
+        let first = Board::construct("73463244621316374157434");
+        let second = Board::construct("257771314744647214174561");
+        assert_eq!(label(first, &mut table), Ok(WeakScore::Loss));
+        assert_eq!(label(second, &mut table), Ok(WeakScore::Loss));
+        // the first player has four in the first column
+        let over = Board::construct("1212121");
+        assert_eq!(label(over, &mut table), Err(DatasetError::GameOver));
+        let input = format!(
+            "{}\n\n{}\nb,b,win\n{}\n",
+            format_row(&first, WeakScore::Loss),
+            format_row(&second, WeakScore::Draw),
+            format_row(&over, WeakScore::Win)
+        );
+        let verification = verify(Cursor::new(input), &mut table).unwrap();
+        assert_eq!(verification.rows, 4);
+        assert_eq!(
+            verification.errors,
+            vec![
+                (4, DatasetError::FieldCount(3)),
+                (5, DatasetError::GameOver)
+            ]
+        );
+        assert_eq!(verification.disagreements.len(), 1);
+        let disagreement = &verification.disagreements[0];
+        assert_eq!((disagreement.line, disagreement.board), (3, second));
+        assert_eq!(
+            disagreement.to_string(),
+            "line 3: expected draw but solved loss"
+        );
+    }
+}
@@ -0,0 +1,419 @@
+use super::board::Board;
+use super::network::{Network, NetworkPlayer};
+use super::rng::Rng;
+use super::solver::{solve_bounded, Search};
+use super::table::Table;
+use std::path::Path;
+
+// anything that can pick moves for a position. Columns are 0 indexed and scores
+// use the same convention as `solve`: positive means the player to move wins.
+pub trait Engine {
+    fn name(&self) -> String;
+
+    // forget anything learnt from a previous game
+    fn new_game(&mut self);
+
+    // the column to play, the position must have at least one legal move
+    fn best_move(&mut self, position: Board) -> u8;
+
+    fn evaluate(&mut self, position: Board) -> i32;
+}
+
+impl<E: Engine + ?Sized> Engine for Box<E> {
+    fn name(&self) -> String {
+        (**self).name()
+    }
+
+    fn new_game(&mut self) {
+        (**self).new_game()
+    }
+
+    fn best_move(&mut self, position: Board) -> u8 {
+        (**self).best_move(position)
+    }
+
+    fn evaluate(&mut self, position: Board) -> i32 {
+        (**self).evaluate(position)
+    }
+}
+
+// the exact solver
+pub struct Solver {
+    table: Table,
+    order: Option<[u8; Board::WIDTH as usize]>,
+    // key, score and column of the last position solved, callers usually want both the score
+    // and the move for the same position
+    last: Option<(u64, i32, u8)>,
+}
+
+impl Solver {
+    pub fn new() -> Solver {
+        Solver::with_table(Table::new())
+    }
+
+    pub fn with_table(table: Table) -> Solver {
+        Solver {
+            table,
+            order: None,
+            last: None,
+        }
+    }
+
+    // tries columns in `order` rather than from the centre out when moves look equally good
+    pub fn with_order(table: Table, order: [u8; Board::WIDTH as usize]) -> Solver {
+        Solver {
+            order: Some(order),
+            ..Solver::with_table(table)
+        }
+    }
+
+    fn solve(&mut self, position: Board) -> (i32, u8) {
+        if let Some((key, score, col)) = self.last {
+            if key == position.hash() {
+                return (score, col);
+            }
+        }
+        let mut search = Search {
+            order: self.order,
+            ..Search::new()
+        };
+        let (score, _max, action) = solve_bounded(position, &mut self.table, &mut search);
+        // no move comes back when every move is as bad as it gets
+        let col = if action >= 0 && position.can_play(action as u8) {
+            action as u8
+        } else {
+            Board::columns(position.nonlosing_moves())
+                .next()
+                .unwrap_or_else(|| position.possible_move())
+        };
+        self.last = Some((position.hash(), score, col));
+        (score, col)
+    }
+
+    pub fn table(&mut self) -> &mut Table {
+        &mut self.table
+    }
+}
+
+impl Default for Solver {
+    fn default() -> Solver {
+        Solver::new()
+    }
+}
+
+impl Engine for Solver {
+    fn name(&self) -> String {
+        match self.order {
+            Some(order) => format!("solver/{}", order_text(&order)),
+            None => String::from("solver"),
+        }
+    }
+
+    fn new_game(&mut self) {
+        self.table.clear();
+        self.last = None;
+    }
+
+    fn best_move(&mut self, position: Board) -> u8 {
+        self.solve(position).1
+    }
+
+    fn evaluate(&mut self, position: Board) -> i32 {
+        self.solve(position).0
+    }
+}
+
+// plays a uniformly random legal column
+pub struct RandomPlayer {
+    seed: u64,
+    rng: Rng,
+}
+
+impl RandomPlayer {
+    pub fn new(seed: u64) -> RandomPlayer {
+        RandomPlayer {
+            seed,
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl Engine for RandomPlayer {
+    fn name(&self) -> String {
+        String::from("random")
+    }
+
+    fn new_game(&mut self) {
+        self.seed += 1;
+        self.rng = Rng::new(self.seed);
+    }
+
+    fn best_move(&mut self, position: Board) -> u8 {
+        random_col(&mut self.rng, position, position.possible())
+    }
+
+    fn evaluate(&mut self, _position: Board) -> i32 {
+        0
+    }
+}
+
+// takes immediate wins, otherwise blocks the opponent and avoids playing
+// underneath one of their threats
+pub struct OnePly {
+    rng: Option<Rng>,
+}
+
+impl OnePly {
+    // always prefers the centre most column
+    pub fn new() -> OnePly {
+        OnePly { rng: None }
+    }
+
+    // picks randomly between equally good columns
+    pub fn randomized(seed: u64) -> OnePly {
+        OnePly {
+            rng: Some(Rng::new(seed)),
+        }
+    }
+}
+
+impl Default for OnePly {
+    fn default() -> OnePly {
+        OnePly::new()
+    }
+}
+
+impl Engine for OnePly {
+    fn name(&self) -> String {
+        String::from("one-ply")
+    }
+
+    fn new_game(&mut self) {}
+
+    fn best_move(&mut self, position: Board) -> u8 {
+        if position.can_win_next() {
+            return position.winning_move();
+        }
+        let mut candidates = position.nonlosing_moves();
+        if candidates == 0 {
+            // lost anyway, just play something
+            candidates = position.possible();
+        }
+        match self.rng.as_mut() {
+            Some(rng) => random_col(rng, position, candidates),
+            None => Board::columns(candidates)
+                .next()
+                .unwrap_or_else(|| position.possible_move()),
+        }
+    }
+
+    fn evaluate(&mut self, position: Board) -> i32 {
+        if position.can_win_next() {
+            ((Board::WIDTH * Board::HEIGHT + 1 - position.nb_moves()) / 2) as i32
+        } else if position.nonlosing_moves() == 0 {
+            -(((Board::WIDTH * Board::HEIGHT - position.nb_moves()) / 2) as i32)
+        } else {
+            0
+        }
+    }
+}
+
+// plays a random column instead of asking the wrapped engine some fraction of the time
+pub struct Weakened<E: Engine> {
+    engine: E,
+    blunder_rate: f64,
+    rng: Rng,
+}
+
+impl<E: Engine> Weakened<E> {
+    pub fn new(engine: E, blunder_rate: f64, seed: u64) -> Weakened<E> {
+        Weakened {
+            engine,
+            blunder_rate,
+            rng: Rng::new(seed),
+        }
+    }
+}
+
+impl<E: Engine> Engine for Weakened<E> {
+    fn name(&self) -> String {
+        format!("{}@{}", self.engine.name(), self.blunder_rate)
+    }
+
+    fn new_game(&mut self) {
+        self.engine.new_game();
+    }
+
+    fn best_move(&mut self, position: Board) -> u8 {
+        if self.rng.next_f64() < self.blunder_rate {
+            random_col(&mut self.rng, position, position.possible())
+        } else {
+            self.engine.best_move(position)
+        }
+    }
+
+    fn evaluate(&mut self, position: Board) -> i32 {
+        self.engine.evaluate(position)
+    }
+}
+
+fn order_text(order: &[u8]) -> String {
+    order.iter().map(|&col| (b'1' + col) as char).collect()
+}
+
+// every column once, 1 indexed, like "4352617"
+fn parse_order(text: &str) -> Option<[u8; Board::WIDTH as usize]> {
+    let mut order = [0; Board::WIDTH as usize];
+    if text.len() != order.len() {
+        return None;
+    }
+    for (slot, c) in order.iter_mut().zip(text.bytes()) {
+        *slot = c.checked_sub(b'1').filter(|&col| col < Board::WIDTH)?;
+    }
+    if (0..Board::WIDTH).all(|col| order.contains(&col)) {
+        Some(order)
+    } else {
+        None
+    }
+}
+
+// builds an engine from a short description such as `solver`, `solver:65521`
+// (table capacity), `solver:65521:1234567` (and exploration order), `random`, `one-ply`,
+// `weak:0.2` (solver with blunders) or `mlp:4:weights.mlp` (a network searching 4 moves ahead)
+pub fn from_name(name: &str, seed: u64) -> Option<Box<dyn Engine>> {
+    let mut parts = name.splitn(2, ':');
+    let kind = parts.next()?;
+    let arg = parts.next();
+    let engine: Box<dyn Engine> = match (kind, arg) {
+        ("solver", None) => Box::new(Solver::new()),
+        ("solver", Some(arg)) => {
+            let mut parts = arg.splitn(2, ':');
+            let capacity = parts
+                .next()?
+                .parse()
+                .ok()
+                .filter(|&capacity| capacity > 0)?;
+            let table = Table::with_capacity(capacity);
+            match parts.next() {
+                Some(order) => Box::new(Solver::with_order(table, parse_order(order)?)),
+                None => Box::new(Solver::with_table(table)),
+            }
+        }
+        ("random", None) => Box::new(RandomPlayer::new(seed)),
+        ("one-ply", None) => Box::new(OnePly::randomized(seed)),
+        ("weak", Some(rate)) => Box::new(Weakened::new(Solver::new(), rate.parse().ok()?, seed)),
+        ("mlp", Some(arg)) => {
+            let mut parts = arg.splitn(2, ':');
+            let depth = parts.next()?.parse().ok()?;
+            let network = Network::load(Path::new(parts.next()?)).ok()?;
+            Box::new(NetworkPlayer::new(network, depth))
+        }
+        _ => return None,
+    };
+    Some(engine)
+}
+
+fn random_col(rng: &mut Rng, position: Board, moves: u64) -> u8 {
+    let cols: Vec<u8> = Board::columns(moves).collect();
+    if cols.is_empty() {
+        return position.possible_move();
+    }
+    cols[rng.below(cols.len() as u32) as usize]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::{analyze, solve};
+
+    fn engines() -> Vec<Box<dyn Engine>> {
+        vec![
+            Box::new(Solver::new()),
+            Box::new(OnePly::new()),
+            Box::new(OnePly::randomized(3)),
+        ]
+    }
+
+    #[test]
+    fn takes_wins() {
+        for mut engine in engines() {
+            let board = Board::construct("73463244621316374157434");
+            assert_eq!(engine.best_move(board), 1, "{}", engine.name());
+            assert!(engine.evaluate(board) > 0, "{}", engine.name());
+        }
+    }
+
+    #[test]
+    fn blocks_losses() {
+        for mut engine in engines() {
+            let board = Board::construct("53233651363321727745271");
+            assert_eq!(engine.best_move(board), 3, "{}", engine.name());
+        }
+    }
+
+    #[test]
+    fn solver_matches_solve() {
+        let board = Board::construct("257771314744647214174561");
+        let mut solver = Solver::new();
+        let mut table = Table::new();
+        assert_eq!(solver.evaluate(board), solve(board, &mut table).0);
+
+        let action = solver.best_move(board);
+        let mut next = board;
+        next.play_col(action);
+        assert_eq!(-solver.evaluate(next), solver.evaluate(board));
+
+        // the move that comes with the score is always one of the best
+        let mut rng = Rng::new(26);
+        let mut solver = Solver::with_table(Table::with_capacity(1 << 20));
+        let mut edges_first =
+            Solver::with_order(Table::with_capacity(1 << 20), [0, 6, 1, 5, 2, 4, 3]);
+        for _ in 0..200 {
+            let plies = 26 + rng.below(12) as u8;
+            let mut board = Board::new();
+            while board.nb_moves() < plies && !board.is_game_over() {
+                let legal: Vec<u8> = board.legal_columns().collect();
+                board.play_col(legal[rng.below(legal.len() as u32) as usize]);
+            }
+            if board.is_game_over() {
+                continue;
+            }
+            let score = solver.evaluate(board);
+            let scores = analyze(board, &mut table);
+            assert_eq!(scores[solver.best_move(board) as usize], Some(score));
+            assert_eq!(edges_first.evaluate(board), score);
+            assert_eq!(scores[edges_first.best_move(board) as usize], Some(score));
+        }
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(from_name("solver:1021", 0).unwrap().name(), "solver");
+        assert_eq!(
+            from_name("solver:1021:1726354", 0).unwrap().name(),
+            "solver/1726354"
+        );
+        assert!(from_name("solver:0", 0).is_none());
+        assert!(from_name("solver:1021:1726355", 0).is_none());
+        assert!(from_name("solver:1021:172635", 0).is_none());
+        assert_eq!(from_name("weak:0.5", 0).unwrap().name(), "solver@0.5");
+        assert!(from_name("weak", 0).is_none());
+        assert_eq!(
+            from_name("mlp:3:tests/fixtures/tiny.mlp", 0)
+                .unwrap()
+                .name(),
+            "mlp:3"
+        );
+        assert!(from_name("mlp:3:missing.mlp", 0).is_none());
+        assert!(from_name("alphazero", 0).is_none());
+    }
+
+    #[test]
+    fn random_is_legal() {
+        let mut engine = RandomPlayer::new(11);
+        let board = Board::construct("1111112222223333334444445555556666667");
+        for _ in 0..50 {
+            assert_eq!(engine.best_move(board), 6);
+        }
+    }
+}
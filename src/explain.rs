@@ -0,0 +1,547 @@
+// Explains positions with the strategic rules from Victor Allis' thesis "A Knowledge-based
+// Approach of Connect-Four". With the first player to move the second player controls
+// zugzwang, so by default they can answer every move in the same column and claim all the
+// even squares. The rules describe the exceptions they need, and a set of compatible rules
+// that refutes every four the first player could still make proves the second player at
+// least draws. If one of the rules is an Aftereven, a four of their own that the claimed
+// squares complete, they win.
+//
+// That's the only case covered: the rules are all stated for the second player, so positions
+// with the second player to move, or where the first player controls zugzwang with an odd
+// threat and wins, come back as `Unexplained::Unsupported`.
+//
+// Squares use Allis' notation, a1 is the bottom left and g6 the top right. Only the
+// rules named in the thesis are tried so many drawn positions go unexplained, and the
+// search gives up after `BUDGET` steps.
+
+use super::board::Board;
+use std::fmt;
+
+const BUDGET: u32 = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Square {
+    pub col: u8,
+    // 0 is the bottom row
+    pub row: u8,
+}
+
+impl Square {
+    fn mask(&self) -> u64 {
+        Board::square_mask(self.col, self.row)
+    }
+
+    // rows count from 1 in the thesis, so even squares have odd indices here
+    fn is_even(&self) -> bool {
+        self.row % 2 == 1
+    }
+
+    fn above(&self) -> Square {
+        Square {
+            col: self.col,
+            row: self.row + 1,
+        }
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.col) as char, self.row + 1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rule {
+    // the second player answers `lower` with `upper` and gets the even square
+    Claimeven {
+        lower: Square,
+        upper: Square,
+    },
+    // two playable squares, whichever the first player takes the second player takes the other
+    Baseinverse {
+        squares: [Square; 2],
+    },
+    // an even square under an odd one, the first player gets at most one of them
+    Vertical {
+        lower: Square,
+        upper: Square,
+    },
+    // a four of the second player's that the claimevens on its empty squares complete
+    Aftereven {
+        group: [Square; 4],
+        claimevens: Vec<[Square; 2]>,
+    },
+    // two claimeven pairs where the first player gets at most one of the upper squares and
+    // never both squares of a column, leaving the second player a spare move
+    Lowinverse {
+        columns: [[Square; 2]; 2],
+    },
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Rule::Claimeven { lower, upper } => write!(
+                f,
+                "Claimeven {}-{}: answer {} with {}, claiming the even square",
+                lower, upper, lower, upper
+            ),
+            Rule::Baseinverse { squares: [a, b] } => write!(
+                f,
+                "Baseinverse {}+{}: answer either one with the other",
+                a, b
+            ),
+            Rule::Vertical { lower, upper } => write!(
+                f,
+                "Vertical {}-{}: answer {} with {}, the first player can't have both",
+                lower, upper, lower, upper
+            ),
+            Rule::Aftereven { group, claimevens } => {
+                let group: Vec<String> = group.iter().map(|s| s.to_string()).collect();
+                let claimed: Vec<String> = claimevens.iter().map(|c| c[1].to_string()).collect();
+                write!(
+                    f,
+                    "Aftereven {}: claiming {} completes this four, so nothing above it counts",
+                    group.join(" "),
+                    claimed.join(" ")
+                )
+            }
+            Rule::Lowinverse {
+                columns: [[a1, a2], [b1, b2]],
+            } => write!(
+                f,
+                "Lowinverse {}-{} and {}-{}: the first player gets at most one of {} and {} \
+                 and never both squares of a column",
+                a1, a2, b1, b2, a2, b2
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    // the second player can at least draw
+    Draw,
+    Win,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    pub verdict: Verdict,
+    pub rules: Vec<Rule>,
+}
+
+// why a position has no explanation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unexplained {
+    GameOver,
+    // the second player is to move, or no rules were found and the first player has an odd
+    // threat that controls zugzwang. Allis' rules for the first player aren't implemented.
+    Unsupported,
+    // no set of rules was found within the budget
+    NotFound,
+}
+
+impl fmt::Display for Unexplained {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Unexplained::GameOver => write!(f, "the game is already over"),
+            Unexplained::Unsupported => write!(
+                f,
+                "unsupported, only the second player's zugzwang with the first player to move can be explained"
+            ),
+            Unexplained::NotFound => write!(f, "no explanation found"),
+        }
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.verdict {
+            Verdict::Draw => writeln!(f, "the second player can at least draw")?,
+            Verdict::Win => writeln!(f, "the second player wins")?,
+        }
+        if self.rules.is_empty() {
+            writeln!(f, "- the first player has no four left to make")?;
+        }
+        for rule in self.rules.iter() {
+            writeln!(f, "- {}", rule)?;
+        }
+        Ok(())
+    }
+}
+
+// every line of four on the board
+fn groups() -> Vec<[Square; 4]> {
+    let mut groups = Vec::new();
+    let directions: [(i8, i8); 4] = [(1, 0), (0, 1), (1, 1), (1, -1)];
+    for col in 0..Board::WIDTH as i8 {
+        for row in 0..Board::HEIGHT as i8 {
+            for &(dc, dr) in directions.iter() {
+                let (end_col, end_row) = (col + 3 * dc, row + 3 * dr);
+                if end_col >= Board::WIDTH as i8 || end_row < 0 || end_row >= Board::HEIGHT as i8 {
+                    continue;
+                }
+                let mut group = [Square { col: 0, row: 0 }; 4];
+                for (i, square) in group.iter_mut().enumerate() {
+                    *square = Square {
+                        col: (col + i as i8 * dc) as u8,
+                        row: (row + i as i8 * dr) as u8,
+                    };
+                }
+                groups.push(group);
+            }
+        }
+    }
+    groups
+}
+
+fn mask(squares: &[Square]) -> u64 {
+    squares.iter().fold(0, |mask, square| mask | square.mask())
+}
+
+fn covers(group: u64, squares: u64) -> bool {
+    group & squares == squares
+}
+
+struct Candidate {
+    rule: Rule,
+    // squares the rule needs to itself
+    squares: u64,
+    // claimeven pairs, which can be shared with an identical claimeven
+    claimevens: u64,
+    // bit i is set if the rule refutes the first player's group i
+    solves: u128,
+}
+
+impl Candidate {
+    fn new(rule: Rule, groups: &[u64]) -> Candidate {
+        let (squares, claimevens, refutes): (u64, u64, Box<dyn Fn(u64) -> bool>) = match &rule {
+            Rule::Claimeven { lower, upper } => {
+                let upper = upper.mask();
+                (
+                    0,
+                    lower.mask() | upper,
+                    Box::new(move |group| group & upper != 0),
+                )
+            }
+            Rule::Baseinverse { squares } => {
+                let both = mask(squares);
+                (both, 0, Box::new(move |group| covers(group, both)))
+            }
+            Rule::Vertical { lower, upper } => {
+                let both = lower.mask() | upper.mask();
+                (both, 0, Box::new(move |group| covers(group, both)))
+            }
+            Rule::Aftereven { claimevens, .. } => {
+                let uppers: Vec<Square> = claimevens.iter().map(|pair| pair[1]).collect();
+                let claimed = claimevens
+                    .iter()
+                    .fold(0, |all, pair| all | mask(pair.as_ref()));
+                let upper_mask = mask(&uppers);
+                // groups with a square above the aftereven square in each of its columns
+                let above: Vec<u64> = uppers
+                    .iter()
+                    .map(|upper| {
+                        (upper.row + 1..Board::HEIGHT)
+                            .fold(0, |mask, row| mask | Board::square_mask(upper.col, row))
+                    })
+                    .collect();
+                (
+                    0,
+                    claimed,
+                    Box::new(move |group| {
+                        group & upper_mask != 0 || above.iter().all(|above| group & above != 0)
+                    }),
+                )
+            }
+            Rule::Lowinverse { columns } => {
+                let all = mask(&columns[0]) | mask(&columns[1]);
+                let pairs = [
+                    mask(&columns[0]),
+                    mask(&columns[1]),
+                    columns[0][1].mask() | columns[1][1].mask(),
+                ];
+                (
+                    all,
+                    0,
+                    Box::new(move |group| pairs.iter().any(|&pair| covers(group, pair))),
+                )
+            }
+        };
+        let mut solves = 0;
+        for (i, &group) in groups.iter().enumerate() {
+            if refutes(group) {
+                solves |= 1 << i;
+            }
+        }
+        Candidate {
+            rule,
+            squares,
+            claimevens,
+            solves,
+        }
+    }
+
+    fn fits(&self, squares: u64, claimevens: u64) -> bool {
+        self.squares & (squares | claimevens) == 0 && self.claimevens & squares == 0
+    }
+}
+
+fn candidates(board: &Board, groups: &[[Square; 4]], first_groups: &[u64]) -> Vec<Candidate> {
+    let all = board.stones(0) | board.stones(1);
+    let empty = |square: &Square| all & square.mask() == 0;
+    let mut rules = Vec::new();
+    let mut claimevens = Vec::new();
+    for col in 0..Board::WIDTH {
+        for row in 0..Board::HEIGHT - 1 {
+            let lower = Square { col, row };
+            let upper = lower.above();
+            if !empty(&lower) || !empty(&upper) {
+                continue;
+            }
+            if upper.is_even() {
+                claimevens.push([lower, upper]);
+                rules.push(Rule::Claimeven { lower, upper });
+            } else {
+                rules.push(Rule::Vertical { lower, upper });
+            }
+        }
+    }
+    let playable: Vec<Square> = board
+        .legal_columns()
+        .map(|col| Square {
+            col,
+            row: (0..Board::HEIGHT)
+                .find(|&row| all & Board::square_mask(col, row) == 0)
+                .unwrap(),
+        })
+        .collect();
+    for (i, &a) in playable.iter().enumerate() {
+        for &b in playable[i + 1..].iter() {
+            rules.push(Rule::Baseinverse { squares: [a, b] });
+        }
+    }
+    for (i, a) in claimevens.iter().enumerate() {
+        for b in claimevens[i + 1..].iter() {
+            if a[0].col != b[0].col {
+                rules.push(Rule::Lowinverse { columns: [*a, *b] });
+            }
+        }
+    }
+    let second = board.stones(1);
+    for group in groups.iter() {
+        if mask(group) & board.stones(0) != 0 {
+            continue;
+        }
+        let open: Vec<Square> = group
+            .iter()
+            .copied()
+            .filter(|square| second & square.mask() == 0)
+            .collect();
+        let pairs: Option<Vec<[Square; 2]>> = open
+            .iter()
+            .map(|square| claimevens.iter().copied().find(|pair| pair[1] == *square))
+            .collect();
+        if let Some(claimevens) = pairs {
+            if !claimevens.is_empty() {
+                rules.push(Rule::Aftereven {
+                    group: *group,
+                    claimevens,
+                });
+            }
+        }
+    }
+    rules
+        .into_iter()
+        .map(|rule| Candidate::new(rule, first_groups))
+        .filter(|candidate| {
+            candidate.solves != 0 || matches!(candidate.rule, Rule::Aftereven { .. })
+        })
+        .collect()
+}
+
+struct Search<'a> {
+    candidates: &'a [Candidate],
+    chosen: Vec<usize>,
+    budget: u32,
+}
+
+impl<'a> Search<'a> {
+    // picks the unsolved group with the fewest ways to refute it and tries each of them
+    fn run(&mut self, unsolved: u128, squares: u64, claimevens: u64) -> bool {
+        if unsolved == 0 {
+            return true;
+        }
+        if self.budget == 0 {
+            return false;
+        }
+        self.budget -= 1;
+        let mut best: Option<Vec<usize>> = None;
+        let mut groups = unsolved;
+        while groups != 0 {
+            let group = 1 << groups.trailing_zeros();
+            groups &= !group;
+            let options: Vec<usize> = (0..self.candidates.len())
+                .filter(|&i| {
+                    let candidate = &self.candidates[i];
+                    candidate.solves & group != 0 && candidate.fits(squares, claimevens)
+                })
+                .collect();
+            if best
+                .as_ref()
+                .map_or(true, |best| options.len() < best.len())
+            {
+                let done = options.is_empty();
+                best = Some(options);
+                if done {
+                    return false;
+                }
+            }
+        }
+        for i in best.unwrap() {
+            let candidate = &self.candidates[i];
+            self.chosen.push(i);
+            if self.run(
+                unsolved & !candidate.solves,
+                squares | candidate.squares,
+                claimevens | candidate.claimevens,
+            ) {
+                return true;
+            }
+            self.chosen.pop();
+        }
+        false
+    }
+}
+
+// why the second player draws or wins with the first player to move. No set of rules is ever
+// found for a position the first player wins.
+pub fn explain(board: &Board) -> Result<Explanation, Unexplained> {
+    if board.is_game_over() {
+        return Err(Unexplained::GameOver);
+    }
+    if board.nb_moves() % 2 == 1 {
+        return Err(Unexplained::Unsupported);
+    }
+    let groups = groups();
+    // the first player's groups that the second player hasn't already blocked
+    let first_groups: Vec<u64> = groups
+        .iter()
+        .map(|group| mask(group))
+        .filter(|&group| group & board.stones(1) == 0)
+        .collect();
+    let candidates = candidates(board, &groups, &first_groups);
+    let unsolved = (1_u128 << first_groups.len()) - 1;
+    let mut search = Search {
+        candidates: &candidates,
+        chosen: Vec::new(),
+        budget: BUDGET,
+    };
+    let explanation = |search: &Search, verdict| Explanation {
+        verdict,
+        rules: search
+            .chosen
+            .iter()
+            .map(|&i| candidates[i].rule.clone())
+            .collect(),
+    };
+    // a win needs an Aftereven in the set, so try each of them first
+    for (i, candidate) in candidates.iter().enumerate() {
+        if let Rule::Aftereven { .. } = candidate.rule {
+            search.chosen = vec![i];
+            if search.run(
+                unsolved & !candidate.solves,
+                candidate.squares,
+                candidate.claimevens,
+            ) {
+                return Ok(explanation(&search, Verdict::Win));
+            }
+        }
+    }
+    search.chosen = Vec::new();
+    if search.run(unsolved, 0, 0) {
+        return Ok(explanation(&search, Verdict::Draw));
+    }
+    match board.zugzwang() {
+        Some(zugzwang) if zugzwang.winner == 0 => Err(Unexplained::Unsupported),
+        _ => Err(Unexplained::NotFound),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+    use crate::solver::solve;
+    use crate::table::Table;
+    use crate::threats::ZugzwangRule;
+
+    #[test]
+    fn aftereven() {
+        let board = Board::construct("721515617753752335661226776353");
+        let explanation = explain(&board).unwrap();
+        assert_eq!(explanation.verdict, Verdict::Win);
+        let d1 = Square { col: 3, row: 0 };
+        assert!(explanation.rules.contains(&Rule::Claimeven {
+            lower: d1,
+            upper: d1.above()
+        }));
+        let text = explanation.to_string();
+        assert!(text.starts_with("the second player wins\n- Aftereven c4 d4 e4 f4:"));
+        let (score, _) = solve(board, &mut Table::with_capacity(65521));
+        assert!(score < 0);
+    }
+
+    #[test]
+    fn agrees_with_solve() {
+        let mut rng = Rng::new(5);
+        let mut table = Table::with_capacity(1 << 20);
+        let mut explained = 0;
+        for _ in 0..400 {
+            let mut board = Board::new();
+            for _ in 0..28 {
+                // random moves that don't end the game
+                let cols: Vec<u8> = board
+                    .legal_columns()
+                    .filter(|&col| !board.is_winning_move(col))
+                    .collect();
+                if cols.is_empty() {
+                    break;
+                }
+                let col = cols[rng.below(cols.len() as u32) as usize];
+                board.play_col(col);
+            }
+            if board.nb_moves() != 28 || board.can_win_next() {
+                continue;
+            }
+            if let Ok(explanation) = explain(&board) {
+                explained += 1;
+                let (score, _) = solve(board, &mut table);
+                match explanation.verdict {
+                    Verdict::Draw => assert!(score <= 0, "{:?}\n{}", board, explanation),
+                    Verdict::Win => assert!(score < 0, "{:?}\n{}", board, explanation),
+                }
+            }
+        }
+        assert!(explained >= 5, "{}", explained);
+    }
+
+    #[test]
+    fn not_applicable() {
+        // the first player wins from the start
+        assert_eq!(explain(&Board::new()), Err(Unexplained::NotFound));
+        assert_eq!(
+            explain(&Board::construct("4")),
+            Err(Unexplained::Unsupported)
+        );
+        assert_eq!(
+            explain(&Board::construct("1212121")),
+            Err(Unexplained::GameOver)
+        );
+        // the first player controls zugzwang with an odd threat
+        let board = Board::construct("62373256775522232634");
+        assert_eq!(board.zugzwang().unwrap().rule, ZugzwangRule::OddThreat);
+        assert_eq!(explain(&board), Err(Unexplained::Unsupported));
+    }
+}
@@ -0,0 +1,209 @@
+// C entry points for native callers. The header in include/thimblerigger.h is generated
+// from this file with `cbindgen --config cbindgen.toml --output include/thimblerigger.h`.
+
+use super::board::{Board, MoveError};
+use super::solver::{analyze, best_move as find_best_move};
+use super::table::Table;
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int};
+
+pub const THIMBLE_OK: c_int = 0;
+pub const THIMBLE_NULL_POINTER: c_int = 1;
+pub const THIMBLE_INVALID_UTF8: c_int = 2;
+pub const THIMBLE_INVALID_COLUMN: c_int = 3;
+pub const THIMBLE_COLUMN_FULL: c_int = 4;
+pub const THIMBLE_GAME_OVER: c_int = 5;
+
+/// Number of columns, the length of the array filled by `thimble_analyze`.
+pub const THIMBLE_WIDTH: c_int = 7;
+const _: () = assert!(THIMBLE_WIDTH == Board::WIDTH as c_int);
+
+/// Score reported by `thimble_analyze` for a column that can't be played.
+pub const THIMBLE_NO_SCORE: c_int = -1000;
+
+/// Opaque solver handle owning a transposition table. Reuse it between calls to keep the cache warm.
+pub struct ThimbleSolver {
+    table: Table,
+}
+
+/// Creates a solver whose table holds `table_capacity` entries, or the default size when zero.
+/// The handle must be released with `thimble_solver_free`.
+#[no_mangle]
+pub extern "C" fn thimble_solver_new(table_capacity: u64) -> *mut ThimbleSolver {
+    let table = if table_capacity == 0 {
+        Table::new()
+    } else {
+        Table::with_capacity(table_capacity)
+    };
+    Box::into_raw(Box::new(ThimbleSolver { table }))
+}
+
+/// Releases a solver, passing null is a no-op.
+///
+/// # Safety
+/// `solver` must be null or a handle from `thimble_solver_new` that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn thimble_solver_free(solver: *mut ThimbleSolver) {
+    if !solver.is_null() {
+        drop(Box::from_raw(solver));
+    }
+}
+
+/// Solves the position reached by `moves`, a nul terminated string of 1 indexed columns.
+/// On success writes the score for the player to move and the best column (1 indexed).
+///
+/// # Safety
+/// `solver` must be a live handle, `moves` a nul terminated string and the outputs writable.
+#[no_mangle]
+pub unsafe extern "C" fn thimble_solve(
+    solver: *mut ThimbleSolver,
+    moves: *const c_char,
+    score: *mut c_int,
+    best_move: *mut c_int,
+) -> c_int {
+    if score.is_null() || best_move.is_null() {
+        return THIMBLE_NULL_POINTER;
+    }
+    let (solver, board) = match prepare(solver, moves) {
+        Ok(prepared) => prepared,
+        Err(code) => return code,
+    };
+    match find_best_move(board, &mut solver.table) {
+        Some((best_score, col)) => {
+            *score = best_score;
+            *best_move = col as c_int + 1;
+            THIMBLE_OK
+        }
+        None => THIMBLE_GAME_OVER,
+    }
+}
+
+/// Scores every column of the position reached by `moves`, writing `THIMBLE_WIDTH` values to
+/// `scores`. Full columns get `THIMBLE_NO_SCORE`.
+///
+/// # Safety
+/// `solver` must be a live handle, `moves` a nul terminated string and `scores` must have room
+/// for `THIMBLE_WIDTH` values.
+#[no_mangle]
+pub unsafe extern "C" fn thimble_analyze(
+    solver: *mut ThimbleSolver,
+    moves: *const c_char,
+    scores: *mut c_int,
+) -> c_int {
+    if scores.is_null() {
+        return THIMBLE_NULL_POINTER;
+    }
+    let (solver, board) = match prepare(solver, moves) {
+        Ok(prepared) => prepared,
+        Err(code) => return code,
+    };
+    for (col, score) in analyze(board, &mut solver.table).iter().enumerate() {
+        *scores.add(col) = score.unwrap_or(THIMBLE_NO_SCORE);
+    }
+    THIMBLE_OK
+}
+
+/// Clears the solver's transposition table.
+///
+/// # Safety
+/// `solver` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn thimble_solver_clear(solver: *mut ThimbleSolver) -> c_int {
+    match solver.as_mut() {
+        Some(solver) => {
+            solver.table.clear();
+            THIMBLE_OK
+        }
+        None => THIMBLE_NULL_POINTER,
+    }
+}
+
+/// Static description of an error code, never null.
+#[no_mangle]
+pub extern "C" fn thimble_error_message(code: c_int) -> *const c_char {
+    let message: &'static [u8] = match code {
+        THIMBLE_OK => b"ok\0",
+        THIMBLE_NULL_POINTER => b"null pointer\0",
+        THIMBLE_INVALID_UTF8 => b"moves are not valid utf-8\0",
+        THIMBLE_INVALID_COLUMN => b"invalid column\0",
+        THIMBLE_COLUMN_FULL => b"column is full\0",
+        THIMBLE_GAME_OVER => b"game is already over\0",
+        _ => b"unknown error\0",
+    };
+    message.as_ptr() as *const c_char
+}
+
+unsafe fn prepare<'a>(
+    solver: *mut ThimbleSolver,
+    moves: *const c_char,
+) -> Result<(&'a mut ThimbleSolver, Board), c_int> {
+    if solver.is_null() || moves.is_null() {
+        return Err(THIMBLE_NULL_POINTER);
+    }
+    let moves = CStr::from_ptr(moves)
+        .to_str()
+        .map_err(|_| THIMBLE_INVALID_UTF8)?;
+    let board = Board::parse(moves).map_err(|error| match error {
+        MoveError::InvalidColumn(_) => THIMBLE_INVALID_COLUMN,
+        MoveError::ColumnFull(_) => THIMBLE_COLUMN_FULL,
+        MoveError::GameOver(_) => THIMBLE_GAME_OVER,
+    })?;
+    if board.is_game_over() {
+        return Err(THIMBLE_GAME_OVER);
+    }
+    Ok((&mut *solver, board))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+    use std::ptr;
+
+    #[test]
+    fn solve_and_analyze() {
+        unsafe {
+            let solver = thimble_solver_new(65521);
+            let moves = CString::new("53233651363321727745271").unwrap();
+            let (mut score, mut best_move) = (0, 0);
+            let code = thimble_solve(solver, moves.as_ptr(), &mut score, &mut best_move);
+            assert_eq!(code, THIMBLE_OK);
+            assert_eq!(best_move, 4);
+
+            let mut scores = [0; Board::WIDTH as usize];
+            let code = thimble_analyze(solver, moves.as_ptr(), scores.as_mut_ptr());
+            assert_eq!(code, THIMBLE_OK);
+            assert_eq!(scores[3], score);
+            assert!(scores.iter().all(|&s| s == THIMBLE_NO_SCORE || s <= score));
+
+            assert_eq!(thimble_solver_clear(solver), THIMBLE_OK);
+            thimble_solver_free(solver);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        unsafe {
+            let solver = thimble_solver_new(1021);
+            let mut scores = [0; Board::WIDTH as usize];
+            for (moves, expected) in [
+                ("448", THIMBLE_INVALID_COLUMN),
+                ("1111111", THIMBLE_COLUMN_FULL),
+                ("1212121", THIMBLE_GAME_OVER),
+            ]
+            .iter()
+            {
+                let moves = CString::new(*moves).unwrap();
+                let code = thimble_analyze(solver, moves.as_ptr(), scores.as_mut_ptr());
+                assert_eq!(code, *expected);
+                let message = CStr::from_ptr(thimble_error_message(code));
+                assert!(!message.to_bytes().is_empty());
+            }
+            let moves = CString::new("4").unwrap();
+            let code = thimble_analyze(ptr::null_mut(), moves.as_ptr(), scores.as_mut_ptr());
+            assert_eq!(code, THIMBLE_NULL_POINTER);
+            thimble_solver_free(solver);
+            thimble_solver_free(ptr::null_mut());
+        }
+    }
+}
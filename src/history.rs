@@ -0,0 +1,132 @@
+// A game that remembers the moves that produced it, for undo/redo in interactive play and
+// for reporting lines. Use a plain `Board` in the search, copying it is all the history it needs.
+
+use super::board::{Board, MoveError};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct History {
+    board: Board,
+    // 0 indexed columns
+    moves: Vec<u8>,
+    // undone moves, the next one to redo last
+    undone: Vec<u8>,
+}
+
+impl History {
+    pub fn new() -> History {
+        History::default()
+    }
+
+    // same rules as `Board::parse`
+    pub fn from_moves(moves: &str) -> Result<History, MoveError> {
+        let board = Board::parse(moves)?;
+        let moves = moves
+            .chars()
+            .filter_map(|c| c.to_digit(10))
+            .map(|col| col as u8 - 1)
+            .collect();
+        Ok(History {
+            board,
+            moves,
+            undone: Vec::new(),
+        })
+    }
+
+    pub fn board(&self) -> Board {
+        self.board
+    }
+
+    // errors carry the index the move would have had in `moves()`.
+    // Playing the move that would be redone keeps the rest of the redo list.
+    pub fn play(&mut self, col: u8) -> Result<(), MoveError> {
+        let index = self.moves.len();
+        if col >= Board::WIDTH {
+            return Err(MoveError::InvalidColumn(index));
+        }
+        if self.board.is_game_over() {
+            return Err(MoveError::GameOver(index));
+        }
+        if !self.board.can_play(col) {
+            return Err(MoveError::ColumnFull(index));
+        }
+        if self.undone.last() == Some(&col) {
+            self.undone.pop();
+        } else {
+            self.undone.clear();
+        }
+        self.board.play_col(col);
+        self.moves.push(col);
+        Ok(())
+    }
+
+    // takes back the last move and returns its column
+    pub fn undo(&mut self) -> Option<u8> {
+        let col = self.moves.pop()?;
+        self.undone.push(col);
+        // bitboards can't take a stone back, replaying at most 42 moves is cheap enough
+        self.board = Board::new();
+        for &col in self.moves.iter() {
+            self.board.play_col(col);
+        }
+        Some(col)
+    }
+
+    // plays the last undone move again and returns its column
+    pub fn redo(&mut self) -> Option<u8> {
+        let col = self.undone.pop()?;
+        self.board.play_col(col);
+        self.moves.push(col);
+        Some(col)
+    }
+
+    pub fn moves(&self) -> &[u8] {
+        &self.moves
+    }
+
+    // 1 indexed, the format `Board::parse` reads
+    pub fn to_move_string(&self) -> String {
+        self.moves.iter().map(|&col| (b'1' + col) as char).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_redo() {
+        let mut history = History::from_moves("4453").unwrap();
+        assert_eq!(history.moves(), &[3, 3, 4, 2]);
+        assert_eq!(history.undo(), Some(2));
+        assert_eq!(history.undo(), Some(4));
+        assert_eq!(history.board(), Board::construct("44"));
+        assert_eq!(history.redo(), Some(4));
+        assert_eq!(history.to_move_string(), "445");
+
+        // the same move keeps the redo list, a different one drops it
+        history.undo();
+        history.play(4).unwrap();
+        assert_eq!(history.redo(), Some(2));
+        history.undo();
+        history.undo();
+        history.play(0).unwrap();
+        assert_eq!(history.redo(), None);
+        assert_eq!(history.to_move_string(), "441");
+
+        while history.undo().is_some() {}
+        assert_eq!(history.board(), Board::new());
+        assert_eq!(history.redo(), Some(3));
+    }
+
+    #[test]
+    fn errors() {
+        let mut history = History::from_moves("1212121").unwrap();
+        assert_eq!(history.play(2), Err(MoveError::GameOver(7)));
+        history.undo();
+        assert_eq!(history.play(7), Err(MoveError::InvalidColumn(6)));
+        history.play(2).unwrap();
+        let mut history = History::from_moves("111111").unwrap();
+        assert_eq!(history.play(0), Err(MoveError::ColumnFull(6)));
+        assert_eq!(History::from_moves("19"), Err(MoveError::InvalidColumn(1)));
+    }
+}
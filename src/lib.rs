@@ -1,25 +1,149 @@
-mod board;
-mod solver;
-mod sort;
-mod table;
+pub mod arena;
+pub mod batch;
+pub mod board;
+pub mod dataset;
+pub mod engine;
+pub mod explain;
+pub mod ffi;
+pub mod history;
+pub mod network;
+pub mod perft;
+pub mod proof;
+pub mod prooftree;
+pub mod protocol;
+pub mod puzzle;
+pub mod record;
+pub mod reference;
+pub mod refutation;
+pub mod render;
+pub mod review;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod rng;
+pub mod sampler;
+pub mod solver;
+pub mod sort;
+pub mod table;
+pub mod testset;
+pub mod threats;
+pub mod training;
 
 use board::Board;
-use solver::solve;
-
-
+use engine::{Engine, Solver};
+use refutation::{refute, LineEnd};
+use render::Highlights;
+use review::review;
+use solver::{analyze_weak, solve_weak, WeakScore};
 use table::Table;
+
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
 pub fn do_the_magic(state: String) -> String {
     let board = Board::construct(&state);
-    let mut table = Table::new();
-    let (_result, _action) = solve(board, &mut table);
-    let (result, mut action) = solve(board, &mut table);
-    action += 1;
+    let mut engine = Solver::new();
+    let result = engine.evaluate(board);
+    let action = engine.best_move(board) + 1;
     format!("{{\"utility\":{},\"action\":{}}}", result, action)
 }
 
+// svg of the position for the web page, marking the last move, a finished four and any threats.
+// Empty if the moves aren't a legal game.
+#[wasm_bindgen]
+pub fn render_svg(state: String) -> String {
+    let board = match Board::parse(&state) {
+        Ok(board) => board,
+        Err(_) => return String::new(),
+    };
+    // every move checked out, so the last one is a column digit
+    let last_move = state
+        .chars()
+        .rfind(|c| !c.is_whitespace())
+        .and_then(|col| col.to_digit(10))
+        .map(|col| col as u8 - 1);
+    render::svg(&board, &Highlights::all(last_move))
+}
+
+// the opponent's winning line after playing `column` (1 indexed), or null if it doesn't lose
+#[wasm_bindgen]
+pub fn refutation_line(state: String, column: u8) -> String {
+    let board = Board::construct(&state);
+    let mut table = Table::new();
+    match refute(board, column.wrapping_sub(1), &mut table) {
+        Some(refutation) => format!(
+            "{{\"score\":{},\"line\":\"{}\",\"end\":\"{}\"}}",
+            refutation.score,
+            refutation.move_string(),
+            match refutation.end {
+                LineEnd::Four => "four",
+                LineEnd::DoubleThreat => "double_threat",
+            }
+        ),
+        None => String::from("null"),
+    }
+}
+
+// per move review of a game after the first `skip` moves, columns are 1 indexed
+#[wasm_bindgen]
+pub fn review_game(state: String, skip: usize) -> String {
+    let mut table = Table::new();
+    let report = match review(&state, skip, &mut table) {
+        Ok(report) => report,
+        Err(error) => return format!("{{\"error\":\"{}\"}}", error),
+    };
+    let optional = |value: Option<String>| value.unwrap_or_else(|| String::from("null"));
+    let moves: Vec<String> = report
+        .moves
+        .iter()
+        .map(|m| {
+            format!(
+                "{{\"column\":{},\"score\":{},\"best_score\":{},\"best_column\":{},\"quality\":\"{}\"}}",
+                m.column + 1,
+                m.score,
+                m.best_score,
+                optional(m.best_column.map(|col| (col + 1).to_string())),
+                m.quality
+            )
+        })
+        .collect();
+    let accuracy: Vec<String> = (0..2)
+        .map(|player| optional(report.accuracy(player).map(|a| format!("{:.1}", a))))
+        .collect();
+    format!(
+        "{{\"first\":{},\"moves\":[{}],\"accuracy\":[{}]}}",
+        report.first,
+        moves.join(","),
+        accuracy.join(",")
+    )
+}
+
+// win, draw or loss for the player to move and for each column, null for full columns.
+// Much cheaper than do_the_magic, meant for highlighting moves.
+#[wasm_bindgen]
+pub fn weak_analysis(state: String) -> String {
+    let board = Board::construct(&state);
+    if board.is_game_over() {
+        return String::from("null");
+    }
+    let name = |score: Option<WeakScore>| match score {
+        Some(WeakScore::Win) => "\"win\"",
+        Some(WeakScore::Draw) => "\"draw\"",
+        Some(WeakScore::Loss) => "\"loss\"",
+        None => "null",
+    };
+    let mut table = Table::new();
+    let result = solve_weak(board, &mut table);
+    let columns: Vec<&str> = analyze_weak(board, &mut table)
+        .iter()
+        .map(|&score| name(score))
+        .collect();
+    format!(
+        "{{\"result\":{},\"columns\":[{}]}}",
+        name(Some(result)),
+        columns.join(",")
+    )
+}
+
 #[wasm_bindgen]
 pub fn add(a: u32, b: u32) -> u32 {
     a + b
@@ -1,19 +1,28 @@
 // I wonder whether 8 capacity would make things
 // faster?
 
-mod board;
-mod solver;
-mod sort;
-mod table;
-
-use board::Board;
-use solver::solve;
 use std::fs;
+use std::io::{self, BufRead};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
-use table::Table;
-
+use thimblerigger::arena::{play_out, run_match, MatchConfig};
+use thimblerigger::batch::{solve_file, solve_lines, BatchConfig, Checkpoint};
+use thimblerigger::board::Board;
+use thimblerigger::dataset::{format_row, label, verify};
+use thimblerigger::engine::{self, Engine};
+use thimblerigger::explain::explain;
+use thimblerigger::perft::perft;
+use thimblerigger::proof::{ProofResult, ProofSearch};
+use thimblerigger::prooftree::{self, format_tree};
+use thimblerigger::protocol::Protocol;
+use thimblerigger::puzzle::{generate, PuzzleConfig, Source};
+use thimblerigger::review::review;
+use thimblerigger::table::Table;
+use thimblerigger::testset::{write_suites, SUITES};
+use thimblerigger::training::{sample, write_npy, ExportConfig, Sampling};
 
-fn generate_cache(board: String, depth: u8, table: &mut Table) {
+fn generate_cache<E: Engine>(board: String, depth: u8, engine: &mut E) {
     if depth == 0 {
         return;
     }
@@ -21,50 +30,49 @@ fn generate_cache(board: String, depth: u8, table: &mut Table) {
         let mut board2 = Board::construct(&board);
         board2.play_col(i);
         let now = Instant::now();
-        let (result, mut action) = solve(board2, table);
-        action += 1;
+        let result = engine.evaluate(board2);
+        let action = engine.best_move(board2) + 1;
         if now.elapsed().as_secs_f32() > 0.25 {
             println!("\"{}{}\":[{},{}]", board, i + 1, action, result,);
         }
         if !board2.can_win_next() {
             let mut board3 = board2;
-            board3.play_col((action - 1) as u8);
-            generate_cache(format!("{}{}{}", board, i + 1, action), depth - 1, table);
+            board3.play_col(action - 1);
+            generate_cache(format!("{}{}{}", board, i + 1, action), depth - 1, engine);
         }
     }
 }
 
-pub fn do_the_magic(state: &str) -> (i32, i32) {
+pub fn do_the_magic<E: Engine>(state: &str, engine: &mut E) -> (i32, i32) {
     let board = Board::construct(state);
-    let mut table = Table::new();
-    let (result, mut action) = solve(board, &mut table);
-    action += 1;
+    let result = engine.evaluate(board);
+    let action = engine.best_move(board) as i32 + 1;
     (result, action)
 }
 
-fn play_game(init: &str) {
-    let mut table = Table::new();
-    let mut board = Board::construct(init);
+fn play_game<E: Engine>(init: &str, engine: &mut E) {
+    engine.new_game();
+    let board = Board::construct(init);
     board.display();
-    let mut stop: bool = board.can_win_next();
-    while !stop {
+    let mut moves = String::new();
+    play_out(board, &mut moves, |mut board| {
         let now = Instant::now();
-        let (result, mut action) = solve(board, &mut table);
-        action += 1;
-        board.play_col(action as u8);
+        let result = engine.evaluate(board);
+        let action = engine.best_move(board);
+        board.play_col(action);
         board.display();
         println!(
             "{},{},{},{}",
-            action,
+            action + 1,
             result,
             now.elapsed().as_millis(),
             board.nb_moves() / 2
         );
-        stop = board.can_win_next();
-    }
+        action
+    });
 }
 
-fn evaluate_test_sets() {
+fn evaluate_test_sets<E: Engine>(engine: &mut E) {
     let files = vec![
         "test_cases/Test_L3_R1",
         "test_cases/Test_L2_R1",
@@ -77,7 +85,7 @@ fn evaluate_test_sets() {
     for file in files {
         println!("calculating for {}", file);
         let mut count = 0;
-        let mut table = Table::new();
+        engine.new_game();
         let now = Instant::now();
         for line in fs::read_to_string(file).unwrap().lines() {
             count += 1;
@@ -88,7 +96,8 @@ fn evaluate_test_sets() {
             //let min = -100;
             //let max = 100;
             //let result = thimblerigger::negamax(board, &mut table, min, max);
-            let (result, action) = solve(board, &mut table);
+            let result = engine.evaluate(board);
+            let action = engine.best_move(board);
             //table.clear();
             if expect != result {
                 println!(
@@ -119,11 +128,301 @@ fn evaluate_test_sets() {
     }
 }
 
+fn engine_from_name(name: &str, seed: u64) -> Box<dyn Engine> {
+    match engine::from_name(name, seed) {
+        Some(engine) => engine,
+        None => panic!(
+            "unknown engine {}, expected solver[:capacity[:order]], random, one-ply, weak:<rate> or \
+             mlp:<depth>:<weights file>",
+            name
+        ),
+    }
+}
+
+fn play_match(args: &[String]) {
+    let mut engine = engine_from_name(args.get(2).map_or("solver", |name| name.as_str()), 1);
+    let mut opponent = engine_from_name(args.get(3).map_or("one-ply", |name| name.as_str()), 2);
+    let config = MatchConfig {
+        games: args.get(4).map_or(100, |games| games.parse().unwrap()),
+        opening_length: args.get(5).map_or(4, |length| length.parse().unwrap()),
+        seed: args.get(6).map_or(0, |seed| seed.parse().unwrap()),
+    };
+    let result = run_match(&config, &mut engine, &mut opponent);
+    for game in result.games.iter() {
+        println!(
+            "{} {} {} {:?}",
+            game.first, game.second, game.moves, game.outcome
+        );
+    }
+    println!(
+        "{} vs {}: +{} -{} ={} | score {:.3} | elo {:+.0} +/- {:.0}",
+        engine.name(),
+        opponent.name(),
+        result.wins,
+        result.losses,
+        result.draws,
+        result.score(),
+        result.elo(),
+        result.elo_margin()
+    );
+}
+
+// puzzles <count> [seed] [min mate] [max mate] [random|self-play], one JSON object per line
+fn generate_puzzles(args: &[String]) {
+    let number = |index: usize, default: u64| {
+        args.get(index)
+            .map_or(default, |value| value.parse().unwrap())
+    };
+    let small = |index: usize, default: u8| {
+        args.get(index).map_or(default, |value| {
+            value
+                .parse()
+                .unwrap_or_else(|_| panic!("expected a number up to 255, not {}", value))
+        })
+    };
+    let defaults = PuzzleConfig::default();
+    let config = PuzzleConfig {
+        count: number(2, defaults.count as u64) as usize,
+        seed: number(3, defaults.seed),
+        mate_in: (small(4, defaults.mate_in.0), small(5, defaults.mate_in.1)),
+        source: match args.get(6).map(|source| source.as_str()) {
+            Some("random") => Source::Random,
+            _ => Source::SelfPlay,
+        },
+        ..defaults
+    };
+    let puzzles = generate(&config, &mut Table::new()).unwrap_or_else(|error| panic!("{}", error));
+    for puzzle in puzzles {
+        println!("{}", puzzle.to_json());
+    }
+}
+
+// export <file> [random|self-play|exhaustive] [count] [min plies] [max plies] [seed] [mirror],
+// solved positions as a .npy file
+fn export_training_data(args: &[String]) {
+    let file = args.get(2).expect("export <file>");
+    let number = |index: usize, default: u64| {
+        args.get(index)
+            .map_or(default, |value| value.parse().unwrap())
+    };
+    let ply = |index: usize, default: u8| {
+        args.get(index).map_or(default, |value| {
+            value
+                .parse()
+                .unwrap_or_else(|_| panic!("expected a number of plies up to 42, not {}", value))
+        })
+    };
+    let defaults = ExportConfig::default();
+    let config = ExportConfig {
+        sampling: match args.get(3).map(|sampling| sampling.as_str()) {
+            Some("random") => Sampling::Random,
+            Some("exhaustive") => Sampling::Exhaustive,
+            _ => Sampling::SelfPlay,
+        },
+        count: number(4, defaults.count as u64) as usize,
+        plies: (ply(5, defaults.plies.0), ply(6, defaults.plies.1)),
+        seed: number(7, defaults.seed),
+        mirror: args.get(8).is_some_and(|mirror| mirror == "mirror"),
+        ..defaults
+    };
+    let records = sample(&config, &mut Table::new()).unwrap_or_else(|error| panic!("{}", error));
+    let output = io::BufWriter::new(fs::File::create(file).unwrap());
+    write_npy(&records, output).unwrap();
+    println!("{} positions written to {}", records.len(), file);
+}
+
+// prove <moves> [max megabytes] [tree file], whether the player to move wins. A win is written
+// to the tree file as a proof tree that check-proof can verify.
+fn prove_position(args: &[String]) {
+    let moves = args.get(2).map_or("", |moves| moves.as_str());
+    let board = Board::parse(moves).unwrap_or_else(|error| panic!("{}", error));
+    let megabytes: usize = args.get(3).map_or(1024, |max| max.parse().unwrap());
+    let mut search = ProofSearch::new(megabytes << 20);
+    let now = Instant::now();
+    let proof = search.prove(board);
+    let result = match proof {
+        ProofResult::Proved => "the player to move wins",
+        ProofResult::Disproved => "the player to move doesn't win",
+        ProofResult::Unknown => "unknown",
+    };
+    println!(
+        "{} ({} positions, {} ms)",
+        result,
+        search.positions(),
+        now.elapsed().as_millis()
+    );
+    if let (ProofResult::Proved, Some(file)) = (proof, args.get(4)) {
+        let lines = search.strategy(board).unwrap();
+        fs::write(file, format_tree(moves, &lines)).unwrap();
+        println!("{} lines written to {}", lines.len(), file);
+    }
+}
+
+// solve <engine> <moves> [tree file], a win is also written to the tree file as a proof tree
+// from the exact solver whatever the engine
+fn write_solver_tree(moves: &str, file: &str) {
+    let board = Board::parse(moves).unwrap_or_else(|error| panic!("{}", error));
+    match prooftree::solver_strategy(board, &mut Table::new()) {
+        Some(lines) => {
+            fs::write(file, format_tree(moves, &lines)).unwrap();
+            println!("{} lines written to {}", lines.len(), file);
+        }
+        None => println!("the player to move doesn't win, no proof tree written"),
+    }
+}
+
+fn explain_position(moves: &str) {
+    let board = Board::parse(moves).unwrap_or_else(|error| panic!("{}", error));
+    print!("{}", board);
+    match explain(&board) {
+        Ok(explanation) => print!("{}", explanation),
+        Err(unexplained) => println!("{}", unexplained),
+    }
+}
+
+// batch <input|-> [output] [workers], without an output file the results go to stdout and
+// can't be resumed
+fn solve_batch(args: &[String]) {
+    let input = args.get(2).map_or("-", |input| input.as_str());
+    let mut config = BatchConfig::default();
+    if let Some(workers) = args.get(4) {
+        config.workers = workers.parse().unwrap();
+    }
+    let done = match args.get(3) {
+        Some(output) => solve_file(input, Path::new(output), &config),
+        None if input == "-" => solve_lines(
+            io::BufReader::new(io::stdin()),
+            io::stdout(),
+            &config,
+            Checkpoint::default(),
+            |_| Ok(()),
+        ),
+        None => fs::File::open(input).and_then(|file| {
+            solve_lines(
+                io::BufReader::new(file),
+                io::stdout(),
+                &config,
+                Checkpoint::default(),
+                |_| Ok(()),
+            )
+        }),
+    };
+    if let Err(error) = done {
+        eprintln!("batch failed: {}", error);
+        std::process::exit(1);
+    }
+}
+
+// dataset verify <file> re-solves every row of a UCI data set file, dataset label turns move
+// strings from stdin into rows
+fn dataset(args: &[String]) {
+    let mut table = Table::new();
+    match args.get(2).map(|command| command.as_str()) {
+        Some("verify") => {
+            let file = args.get(3).expect("dataset verify <file>");
+            let input = io::BufReader::new(fs::File::open(file).unwrap());
+            let verification = verify(input, &mut table).unwrap();
+            for (line, error) in verification.errors.iter() {
+                println!("line {}: {}", line, error);
+            }
+            for disagreement in verification.disagreements.iter() {
+                println!("{}", disagreement);
+            }
+            println!(
+                "{} rows, {} disagreements, {} errors",
+                verification.rows,
+                verification.disagreements.len(),
+                verification.errors.len()
+            );
+        }
+        Some("label") => {
+            for line in io::stdin().lock().lines() {
+                let line = line.unwrap();
+                let moves = line.split_whitespace().next().unwrap_or("");
+                let board = match Board::parse(moves) {
+                    Ok(board) => board,
+                    Err(error) => {
+                        eprintln!("{}: {}", moves, error);
+                        continue;
+                    }
+                };
+                match label(board, &mut table) {
+                    Ok(outcome) => println!("{}", format_row(&board, outcome)),
+                    Err(error) => eprintln!("{}: {}", moves, error),
+                }
+            }
+        }
+        _ => eprintln!("expected dataset verify <file> or dataset label"),
+    }
+}
+
 fn main() {
-    //evaluate_test_sets();
-    println!("Hello, world!");
-    //let mut table = Table::new();
-    //let result = do_the_magic("444447");
-    //println!("{:?}", result)
-    play_game("444345443332565356")
+    let args: Vec<String> = std::env::args().collect();
+    let engine = || engine_from_name(args.get(2).map_or("solver", |name| name.as_str()), 0);
+    match args.get(1).map(|command| command.as_str()) {
+        Some("match") => play_match(&args),
+        Some("protocol") => {
+            let output = Arc::new(Mutex::new(io::stdout()));
+            Protocol::new(Table::new(), output).run(io::stdin().lock());
+        }
+        Some("bench") => evaluate_test_sets(&mut engine()),
+        Some("testsets") => {
+            // testsets [directory] [count] [seed], the defaults make the files bench reads
+            let directory = args
+                .get(2)
+                .map_or("test_cases", |directory| directory.as_str());
+            let count = args.get(3).map_or(1000, |count| count.parse().unwrap());
+            let seed = args.get(4).map_or(0, |seed| seed.parse().unwrap());
+            let written = write_suites(Path::new(directory), count, seed).unwrap();
+            for (suite, written) in SUITES.iter().zip(written) {
+                if written < count {
+                    println!("only found {} positions for {}", written, suite.name());
+                }
+            }
+        }
+        Some("solve") => {
+            let state = args.get(3).map_or("", |state| state.as_str());
+            println!("{:?}", do_the_magic(state, &mut engine()));
+            if let Some(file) = args.get(4) {
+                write_solver_tree(state, file);
+            }
+        }
+        Some("cache") => {
+            let depth = args.get(3).map_or(2, |depth| depth.parse().unwrap());
+            generate_cache(String::new(), depth, &mut engine());
+        }
+        Some("review") => {
+            let moves = args.get(2).map_or("", |moves| moves.as_str());
+            let skip = args.get(3).map_or(0, |skip| skip.parse().unwrap());
+            match review(moves, skip, &mut Table::new()) {
+                Ok(report) => print!("{}", report),
+                Err(error) => println!("{}", error),
+            }
+        }
+        Some("puzzles") => generate_puzzles(&args),
+        Some("perft") => {
+            // perft <depth> [mirror]
+            let depth = args.get(2).map_or(8, |depth| depth.parse().unwrap());
+            let mirror = args.get(3).is_some_and(|mirror| mirror == "mirror");
+            for count in perft(depth, mirror) {
+                println!("{} {} {}", count.ply, count.sequences, count.positions);
+            }
+        }
+        Some("prove") => prove_position(&args),
+        Some("check-proof") => {
+            let file = args.get(2).expect("check-proof <file>");
+            match prooftree::verify(&fs::read_to_string(file).unwrap()) {
+                Ok(lines) => println!("verified, {} lines all end in a four", lines),
+                Err(error) => {
+                    println!("not a proof: {}", error);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some("export") => export_training_data(&args),
+        Some("dataset") => dataset(&args),
+        Some("batch") => solve_batch(&args),
+        Some("explain") => explain_position(args.get(2).map_or("", |moves| moves.as_str())),
+        _ => play_game("444345443332565356", &mut engine()),
+    }
 }
@@ -0,0 +1,326 @@
+// A small fully connected network that estimates the score of a position, for searches too
+// deep to solve exactly. Plain Rust on the CPU, the weights come from a text file:
+//
+//   # comments start with a hash
+//   mlp 98 16 1
+//   <weights of the first layer, one row of 98 per output>
+//   <16 biases of the first layer>
+//   <then the same for every following layer>
+//
+// The 98 inputs are the bits of the player to move's bitboard followed by the opponent's, bit
+// col * 7 + row with row 0 at the bottom, the same layout `training` exports. The spare bit on
+// top of each column is always 0. Hidden layers use ReLU and the single output is the score
+// for the player to move, on the same scale as `solve`.
+
+use super::board::Board;
+use super::engine::Engine;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// bits of a bitboard, spare row included
+const SQUARES: usize = (Board::WIDTH * (Board::HEIGHT + 1)) as usize;
+pub const INPUTS: usize = 2 * SQUARES;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum NetworkError {
+    MissingHeader,
+    InvalidNumber(String),
+    // the first layer has to take INPUTS and the last give a single output
+    InvalidSizes(Vec<usize>),
+    // how many numbers were expected and how many were found
+    ValueCount { expected: usize, found: usize },
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetworkError::MissingHeader => write!(f, "expected a header like \"mlp 98 16 1\""),
+            NetworkError::InvalidNumber(number) => write!(f, "invalid number {:?}", number),
+            NetworkError::InvalidSizes(sizes) => write!(
+                f,
+                "layer sizes {:?} should start with {} and end with 1",
+                sizes, INPUTS
+            ),
+            NetworkError::ValueCount { expected, found } => {
+                write!(
+                    f,
+                    "expected {} weights and biases but found {}",
+                    expected, found
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Layer {
+    inputs: usize,
+    // one row of `inputs` per output
+    weights: Vec<f32>,
+    biases: Vec<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Network {
+    layers: Vec<Layer>,
+}
+
+impl Network {
+    pub fn parse(text: &str) -> Result<Network, NetworkError> {
+        let mut lines = text
+            .lines()
+            .filter(|line| !line.trim_start().starts_with('#') && !line.trim().is_empty());
+        let mut header = lines.next().unwrap_or("").split_whitespace();
+        if header.next() != Some("mlp") {
+            return Err(NetworkError::MissingHeader);
+        }
+        let sizes = header
+            .map(|size| {
+                size.parse::<usize>()
+                    .map_err(|_| NetworkError::InvalidNumber(size.to_string()))
+            })
+            .collect::<Result<Vec<usize>, NetworkError>>()?;
+        let values = lines
+            .flat_map(|line| line.split_whitespace())
+            .map(|value| {
+                value
+                    .parse::<f32>()
+                    .map_err(|_| NetworkError::InvalidNumber(value.to_string()))
+            })
+            .collect::<Result<Vec<f32>, NetworkError>>()?;
+        if sizes.len() < 2 || sizes[0] != INPUTS || sizes[sizes.len() - 1] != 1 {
+            return Err(NetworkError::InvalidSizes(sizes));
+        }
+        let expected = sizes.windows(2).map(|pair| (pair[0] + 1) * pair[1]).sum();
+        if values.len() != expected {
+            return Err(NetworkError::ValueCount {
+                expected,
+                found: values.len(),
+            });
+        }
+        let mut values = values.into_iter();
+        let layers = sizes
+            .windows(2)
+            .map(|pair| Layer {
+                inputs: pair[0],
+                weights: values.by_ref().take(pair[0] * pair[1]).collect(),
+                biases: values.by_ref().take(pair[1]).collect(),
+            })
+            .collect();
+        Ok(Network { layers })
+    }
+
+    pub fn load(path: &Path) -> io::Result<Network> {
+        let text = fs::read_to_string(path)?;
+        Network::parse(&text).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    pub fn features(position: &Board) -> [f32; INPUTS] {
+        let mut features = [0.0; INPUTS];
+        let player = position.nb_moves() % 2;
+        let stones = [position.stones(player), position.stones(1 - player)];
+        for (i, feature) in features.iter_mut().enumerate() {
+            if stones[i / SQUARES] >> (i % SQUARES) & 1 != 0 {
+                *feature = 1.0;
+            }
+        }
+        features
+    }
+
+    pub fn forward(&self, input: &[f32]) -> f32 {
+        let mut activations = input.to_vec();
+        for (i, layer) in self.layers.iter().enumerate() {
+            let mut outputs: Vec<f32> = layer
+                .weights
+                .chunks(layer.inputs)
+                .zip(layer.biases.iter())
+                .map(|(row, bias)| {
+                    row.iter()
+                        .zip(activations.iter())
+                        .map(|(w, a)| w * a)
+                        .sum::<f32>()
+                        + bias
+                })
+                .collect();
+            if i + 1 < self.layers.len() {
+                for output in outputs.iter_mut() {
+                    *output = output.max(0.0);
+                }
+            }
+            activations = outputs;
+        }
+        activations[0]
+    }
+
+    // estimated score for the player to move
+    pub fn evaluate(&self, position: &Board) -> f32 {
+        self.forward(&Network::features(position))
+    }
+
+    // like `negamax` in the solver but stops `depth` moves ahead and asks the network there.
+    // Wins and losses that show up before then are scored exactly.
+    pub fn negamax(&self, position: Board, depth: u8, mut alpha: f32, beta: f32) -> f32 {
+        let nb_moves = position.nb_moves();
+        if position.can_win_next() {
+            return ((Board::WIDTH * Board::HEIGHT + 1 - nb_moves) / 2) as f32;
+        }
+        let possible = position.nonlosing_moves();
+        if possible == 0 {
+            return -(((Board::WIDTH * Board::HEIGHT - nb_moves) / 2) as f32);
+        }
+        if nb_moves >= Board::WIDTH * Board::HEIGHT - 2 {
+            return 0.0;
+        }
+        if depth == 0 {
+            return self.evaluate(&position);
+        }
+        for col in Board::columns(possible) {
+            let mut next = position;
+            next.play_col(col);
+            let score = -self.negamax(next, depth - 1, -beta, -alpha);
+            if score >= beta {
+                return score;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+
+    // the column with the best score searching `depth` moves ahead, ties go to the centre
+    pub fn best_move(&self, position: Board, depth: u8) -> (u8, f32) {
+        if position.can_win_next() {
+            let score = (Board::WIDTH * Board::HEIGHT + 1 - position.nb_moves()) / 2;
+            return (position.winning_move(), score as f32);
+        }
+        let mut best = (position.possible_move(), f32::NEG_INFINITY);
+        for col in position.legal_columns() {
+            let mut next = position;
+            next.play_col(col);
+            let score = -self.negamax(next, depth.max(1) - 1, f32::NEG_INFINITY, -best.1);
+            if score > best.1 {
+                best = (col, score);
+            }
+        }
+        best
+    }
+}
+
+// plays with a fixed depth search on top of a network
+pub struct NetworkPlayer {
+    network: Network,
+    depth: u8,
+}
+
+impl NetworkPlayer {
+    pub fn new(network: Network, depth: u8) -> NetworkPlayer {
+        NetworkPlayer { network, depth }
+    }
+}
+
+impl Engine for NetworkPlayer {
+    fn name(&self) -> String {
+        format!("mlp:{}", self.depth)
+    }
+
+    fn new_game(&mut self) {}
+
+    fn best_move(&mut self, position: Board) -> u8 {
+        self.network.best_move(position, self.depth).0
+    }
+
+    fn evaluate(&mut self, position: Board) -> i32 {
+        self.network.best_move(position, self.depth).1.round() as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::Table;
+    use crate::training::Record;
+    use std::convert::TryInto;
+
+    const TINY: &str = include_str!("../tests/fixtures/tiny.mlp");
+
+    #[test]
+    fn known_outputs() {
+        let network = Network::parse(TINY).unwrap();
+        // worked out separately in double precision
+        for &(moves, expected) in [
+            ("", 1.088397),
+            ("4453", 1.936392),
+            ("73463244621316374157434", 0.783351),
+            ("257771314744647214174561", 3.250752),
+        ]
+        .iter()
+        {
+            let output = network.evaluate(&Board::construct(moves));
+            assert!((output - expected).abs() < 1e-4, "{} {}", moves, output);
+        }
+        let board = Board::construct("4453");
+        let features = Network::features(&board);
+        assert_eq!(features[3 * 7], 1.0);
+        assert_eq!(features[SQUARES + 3 * 7], 0.0);
+        assert_eq!(features[SQUARES + 3 * 7 + 1], 1.0);
+        assert_eq!(features.iter().sum::<f32>(), 4.0);
+        // the bits of an exported record, the player to move first
+        let bytes = Record::solve(board, &mut Table::with_capacity(1 << 16)).to_bytes();
+        let bitboard = |bytes: &[u8]| u64::from_le_bytes(bytes.try_into().unwrap());
+        let (first, second) = (bitboard(&bytes[0..8]), bitboard(&bytes[8..16]));
+        assert_eq!(bytes[16], 0);
+        for (i, &feature) in features.iter().enumerate() {
+            let stones = if i < SQUARES { first } else { second };
+            assert_eq!(feature, (stones >> (i % SQUARES) & 1) as f32);
+        }
+    }
+
+    #[test]
+    fn invalid_files() {
+        assert_eq!(Network::parse("98 1"), Err(NetworkError::MissingHeader));
+        assert_eq!(
+            Network::parse("mlp 10 1"),
+            Err(NetworkError::InvalidSizes(vec![10, 1]))
+        );
+        assert_eq!(
+            Network::parse("mlp 98 1\n0.5 0.5"),
+            Err(NetworkError::ValueCount {
+                expected: 99,
+                found: 2
+            })
+        );
+        let mut text = TINY.to_string();
+        text.push_str("nan?");
+        assert_eq!(
+            Network::parse(&text),
+            Err(NetworkError::InvalidNumber("nan?".to_string()))
+        );
+    }
+
+    #[test]
+    fn search() {
+        let network = Network::parse(TINY).unwrap();
+        // exact results near the end don't depend on the network
+        let position = Board::construct("73463244621316374157434");
+        assert_eq!(network.best_move(position, 3), (1, 10.0));
+        let position = Board::construct("53233651363321727745271");
+        assert_eq!(network.best_move(position, 1).0, 3);
+        // with no search the move is the one the network likes best
+        let position = Board::construct("4453");
+        let (col, score) = network.best_move(position, 1);
+        let mut next = position;
+        next.play_col(col);
+        assert_eq!(score, -network.evaluate(&next));
+        for other in position.legal_columns() {
+            let mut next = position;
+            next.play_col(other);
+            assert!(-network.evaluate(&next) <= score);
+        }
+        let mut player = NetworkPlayer::new(network, 2);
+        assert_eq!(player.name(), "mlp:2");
+        assert!(position.can_play(player.best_move(position)));
+    }
+}
@@ -0,0 +1,90 @@
+// Counts what can be reached from the empty board ply by ply: the legal move sequences and the
+// distinct positions, told apart by their key, optionally counting a position and its mirror
+// image once. Games stop at a four or a full board, so those positions aren't played on.
+
+use super::board::Board;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Perft {
+    pub ply: u8,
+    pub sequences: u64,
+    pub positions: u64,
+}
+
+fn key(board: &Board, mirror: bool) -> u64 {
+    if mirror {
+        board.hash().min(board.mirror().hash())
+    } else {
+        board.hash()
+    }
+}
+
+// counts for every ply from 0 to `depth`. Positions are expanded a ply at a time along with
+// the number of sequences reaching them, so only one ply is held in memory.
+pub fn perft(depth: u8, mirror: bool) -> Vec<Perft> {
+    let mut counts = Vec::new();
+    let mut level: HashMap<u64, (Board, u64)> = HashMap::new();
+    level.insert(key(&Board::new(), mirror), (Board::new(), 1));
+    for ply in 0..=depth.min(Board::WIDTH * Board::HEIGHT) {
+        counts.push(Perft {
+            ply,
+            sequences: level.values().map(|&(_, sequences)| sequences).sum(),
+            positions: level.len() as u64,
+        });
+        if ply == depth {
+            break;
+        }
+        let mut next = HashMap::with_capacity(level.len() * 4);
+        for (board, sequences) in level.into_values() {
+            if board.last_move_won() {
+                continue;
+            }
+            // with mirroring, the sequences reaching the mirror image follow the mirrored
+            // moves into the same children
+            for col in board.legal_columns() {
+                let mut child = board;
+                child.play_col(col);
+                next.entry(key(&child, mirror)).or_insert((child, 0)).1 += sequences;
+            }
+        }
+        level = next;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn published_counts() {
+        // https://oeis.org/A212693
+        let positions = [1, 7, 49, 238, 1120, 4263, 16422, 54859, 184275];
+        let counts = perft(8, false);
+        assert_eq!(counts.len(), positions.len());
+        for (count, &expected) in counts.iter().zip(positions.iter()) {
+            assert_eq!(count.positions, expected);
+            // every column stays open until six moves have been made
+            if count.ply <= 6 {
+                assert_eq!(count.sequences, 7_u64.pow(count.ply as u32));
+            }
+        }
+        // the seven ways to fill a column leave six moves each
+        assert_eq!(counts[7].sequences, 7_u64.pow(7) - 7);
+    }
+
+    #[test]
+    fn mirrored() {
+        let plain = perft(6, false);
+        let mirrored = perft(6, true);
+        assert_eq!(mirrored[1].positions, 4);
+        // of the 49 positions after two moves only 44 is its own mirror image
+        assert_eq!(mirrored[2].positions, 25);
+        for (plain, mirrored) in plain.iter().zip(mirrored.iter()) {
+            assert_eq!(plain.sequences, mirrored.sequences);
+            assert!(mirrored.positions <= plain.positions);
+            assert!(2 * mirrored.positions > plain.positions);
+        }
+    }
+}
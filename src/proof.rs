@@ -0,0 +1,296 @@
+// Depth-first proof-number search (df-pn): proves or disproves that the player to move wins,
+// without working out by how much. It only ever looks at the moves that don't lose at once,
+// and gives up with `Unknown` once it has stored more positions than it's allowed.
+
+use super::board::Board;
+use std::collections::HashMap;
+use std::mem;
+
+const INFINITY: u32 = u32::MAX / 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofResult {
+    // the player to move wins
+    Proved,
+    // the player to move draws at best
+    Disproved,
+    // ran out of room before either was shown
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProofNumbers {
+    pub proof: u32,
+    pub disproof: u32,
+}
+
+impl ProofNumbers {
+    const PROVED: ProofNumbers = ProofNumbers {
+        proof: 0,
+        disproof: INFINITY,
+    };
+    const DISPROVED: ProofNumbers = ProofNumbers {
+        proof: INFINITY,
+        disproof: 0,
+    };
+    const UNKNOWN: ProofNumbers = ProofNumbers {
+        proof: 1,
+        disproof: 1,
+    };
+}
+
+pub struct ProofSearch {
+    attacker: u8,
+    // numbers for the attacker winning, by position key
+    table: HashMap<u64, ProofNumbers>,
+    max_positions: usize,
+    aborted: bool,
+}
+
+impl ProofSearch {
+    // keeps the table within `max_bytes`. It's allocated up front so it never doubles in the
+    // middle of a search: a power of two of slots, each a key, its numbers and a control byte,
+    // of which 7/8 can be filled. That leaves between half and all of the budget in use.
+    pub fn new(max_bytes: usize) -> ProofSearch {
+        let slot = mem::size_of::<(u64, ProofNumbers)>() + 1;
+        let slots = (max_bytes / slot + 1).next_power_of_two() / 2;
+        let capacity = slots / 8 * 7;
+        // every position on the stack still gets stored once the search gives up
+        let max_positions = capacity.saturating_sub((Board::WIDTH * Board::HEIGHT) as usize + 1);
+        ProofSearch {
+            attacker: 0,
+            table: HashMap::with_capacity(capacity),
+            max_positions,
+            aborted: false,
+        }
+    }
+
+    // the most positions a search stores before giving up
+    pub fn max_positions(&self) -> usize {
+        self.max_positions
+    }
+
+    // positions stored by the last search
+    pub fn positions(&self) -> usize {
+        self.table.len()
+    }
+
+    pub fn prove(&mut self, position: Board) -> ProofResult {
+        self.attacker = position.nb_moves() % 2;
+        self.table.clear();
+        self.aborted = false;
+        let numbers = self.search(position, INFINITY - 1, INFINITY - 1);
+        if numbers.proof == 0 {
+            ProofResult::Proved
+        } else if numbers.disproof == 0 {
+            ProofResult::Disproved
+        } else {
+            ProofResult::Unknown
+        }
+    }
+
+    // the winning strategy found by the last `prove` of `position`, as every line of moves that
+    // ends with the attacker's four: one move where the attacker is to move and every legal
+    // reply where the defender is. None unless that search proved the win.
+    pub fn strategy(&self, position: Board) -> Option<Vec<Vec<u8>>> {
+        if position.nb_moves() % 2 != self.attacker {
+            return None;
+        }
+        let mut lines = Vec::new();
+        if self.collect(position, &mut Vec::new(), &mut lines) {
+            Some(lines)
+        } else {
+            None
+        }
+    }
+
+    fn collect(&self, position: Board, line: &mut Vec<u8>, lines: &mut Vec<Vec<u8>>) -> bool {
+        let mut follow = |col: u8, line: &mut Vec<u8>| {
+            let mut child = position;
+            child.play_col(col);
+            line.push(col);
+            let proved = self.collect(child, line, lines);
+            line.pop();
+            proved
+        };
+        if position.nb_moves() % 2 != self.attacker {
+            // a full board is a draw, so there has to be at least one reply
+            return position.legal_columns().count() > 0
+                && position.legal_columns().all(|col| follow(col, line));
+        }
+        if position.can_win_next() {
+            let mut finished = line.clone();
+            finished.push(position.winning_move());
+            lines.push(finished);
+            return true;
+        }
+        match Board::columns(position.nonlosing_moves()).find(|&col| {
+            let mut child = position;
+            child.play_col(col);
+            self.lookup(&child).proof == 0
+        }) {
+            Some(col) => follow(col, line),
+            None => false,
+        }
+    }
+
+    // positions that are decided without a search: the player to move wins at once, can't
+    // stop the opponent, or the board is as good as full
+    fn terminal(&self, position: &Board) -> Option<ProofNumbers> {
+        let attacking = position.nb_moves() % 2 == self.attacker;
+        let to_move_wins = if position.can_win_next() {
+            true
+        } else if position.nonlosing_moves() == 0 {
+            false
+        } else if position.nb_moves() >= Board::WIDTH * Board::HEIGHT - 2 {
+            // a draw, like the solver scores it
+            return Some(ProofNumbers::DISPROVED);
+        } else {
+            return None;
+        };
+        if to_move_wins == attacking {
+            Some(ProofNumbers::PROVED)
+        } else {
+            Some(ProofNumbers::DISPROVED)
+        }
+    }
+
+    fn lookup(&self, position: &Board) -> ProofNumbers {
+        match self.table.get(&position.hash()) {
+            Some(&numbers) => numbers,
+            None => self.terminal(position).unwrap_or(ProofNumbers::UNKNOWN),
+        }
+    }
+
+    // searches until the position's numbers reach either threshold
+    fn search(&mut self, position: Board, proof_limit: u32, disproof_limit: u32) -> ProofNumbers {
+        if let Some(numbers) = self.terminal(&position) {
+            self.table.insert(position.hash(), numbers);
+            return numbers;
+        }
+        let attacking = position.nb_moves() % 2 == self.attacker;
+        let children: Vec<Board> = Board::columns(position.nonlosing_moves())
+            .map(|col| {
+                let mut child = position;
+                child.play_col(col);
+                child
+            })
+            .collect();
+        loop {
+            // the attacker needs one child proved, the defender one disproved. `best` is the
+            // child closest to that, `second` the runner up's number.
+            let mut numbers = if attacking {
+                ProofNumbers {
+                    proof: INFINITY,
+                    disproof: 0,
+                }
+            } else {
+                ProofNumbers {
+                    proof: 0,
+                    disproof: INFINITY,
+                }
+            };
+            let mut best = (0, INFINITY);
+            let mut second = INFINITY;
+            for (i, child) in children.iter().enumerate() {
+                let child_numbers = self.lookup(child);
+                let (key, sum) = if attacking {
+                    numbers.proof = numbers.proof.min(child_numbers.proof);
+                    (child_numbers.proof, child_numbers.disproof)
+                } else {
+                    numbers.disproof = numbers.disproof.min(child_numbers.disproof);
+                    (child_numbers.disproof, child_numbers.proof)
+                };
+                if attacking {
+                    numbers.disproof = (numbers.disproof + sum).min(INFINITY);
+                } else {
+                    numbers.proof = (numbers.proof + sum).min(INFINITY);
+                }
+                if key < best.1 {
+                    second = best.1;
+                    best = (i, key);
+                } else if key < second {
+                    second = key;
+                }
+            }
+            if numbers.proof >= proof_limit || numbers.disproof >= disproof_limit || self.aborted {
+                self.table.insert(position.hash(), numbers);
+                return numbers;
+            }
+            if self.table.len() >= self.max_positions {
+                self.aborted = true;
+                return numbers;
+            }
+            let child = children[best.0];
+            let child_numbers = self.lookup(&child);
+            let (child_proof_limit, child_disproof_limit) = if attacking {
+                (
+                    proof_limit.min(second.saturating_add(1)),
+                    disproof_limit - numbers.disproof + child_numbers.disproof,
+                )
+            } else {
+                (
+                    proof_limit - numbers.proof + child_numbers.proof,
+                    disproof_limit.min(second.saturating_add(1)),
+                )
+            };
+            self.search(child, child_proof_limit, child_disproof_limit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+    use crate::solver::solve;
+    use crate::table::Table;
+
+    #[test]
+    fn known_positions() {
+        let mut search = ProofSearch::new(1 << 24);
+        assert!(search.max_positions() > 400_000);
+        let position = Board::construct("73463244621316374157434");
+        assert_eq!(search.prove(position), ProofResult::Proved);
+        assert!(search.strategy(position).is_some());
+        let position = Board::construct("257771314744647214174561");
+        assert_eq!(search.prove(position), ProofResult::Disproved);
+        assert_eq!(search.strategy(position), None);
+        let position = Board::construct("53233651363321727745271");
+        assert_eq!(search.prove(position), ProofResult::Disproved);
+        // too little room to get anywhere
+        let mut search = ProofSearch::new(2048);
+        assert_eq!(search.max_positions(), 56 - 43);
+        let position = Board::construct("16757111614166325423452322");
+        assert_eq!(search.prove(position), ProofResult::Unknown);
+    }
+
+    #[test]
+    fn matches_solve() {
+        let mut rng = Rng::new(49);
+        let mut table = Table::with_capacity(1 << 20);
+        let mut search = ProofSearch::new(1 << 24);
+        let capacity = search.table.capacity();
+        let mut checked = 0;
+        while checked < 300 {
+            let plies = 22 + rng.below(16) as u8;
+            let mut board = Board::new();
+            while board.nb_moves() < plies && !board.is_game_over() {
+                let legal: Vec<u8> = board.legal_columns().collect();
+                board.play_col(legal[rng.below(legal.len() as u32) as usize]);
+            }
+            if board.is_game_over() {
+                continue;
+            }
+            let expected = if solve(board, &mut table).0 > 0 {
+                ProofResult::Proved
+            } else {
+                ProofResult::Disproved
+            };
+            assert_eq!(search.prove(board), expected, "{:?}", board);
+            // the table never grew past its allocation
+            assert_eq!(search.table.capacity(), capacity);
+            checked += 1;
+        }
+    }
+}
@@ -0,0 +1,282 @@
+// Proof trees: a winning strategy written out so it can be checked without trusting the search
+// that found it. The file names the position and then has one line per way the game can go,
+// 1 indexed columns from the position on, each ending with the winner's four:
+//
+//   # the player to move wins
+//   position 73463244621316374157434
+//   2
+//
+// Where the winner is to move every line has to agree on a single column, where the loser is to
+// move every legal column has to show up. `verify` replays all of it on a `Board`.
+//
+// Trees come from the proof-number search (`ProofSearch::strategy`, the `prove` command) or
+// from the exact solver (`solver_strategy`, `solve` with a tree file).
+
+use super::board::{Board, MoveError};
+use super::solver::{best_move, solve};
+use super::table::Table;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum TreeError {
+    MissingPosition,
+    Position(MoveError),
+    // the position is already over
+    GameOver,
+    // 1 indexed line of the file
+    InvalidLine(usize),
+    // the moves leading there, 1 indexed, then the column
+    IllegalMove(String, u8),
+    MoreThanOneMove(String),
+    MissingReply(String, u8),
+    // a line that stops before the four
+    Unfinished(String),
+    MovesAfterWin(String),
+    // the loser makes four on a line
+    LoserWins(String),
+}
+
+fn moves_text(moves: &[u8]) -> String {
+    moves.iter().map(|&col| (b'1' + col) as char).collect()
+}
+
+impl fmt::Display for TreeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TreeError::MissingPosition => write!(f, "expected a position line first"),
+            TreeError::Position(error) => write!(f, "invalid position: {}", error),
+            TreeError::GameOver => write!(f, "the position is already over"),
+            TreeError::InvalidLine(line) => write!(f, "line {} isn't a list of columns", line),
+            TreeError::IllegalMove(moves, col) => {
+                write!(f, "column {} can't be played after {:?}", col + 1, moves)
+            }
+            TreeError::MoreThanOneMove(moves) => {
+                write!(f, "more than one move for the winner after {:?}", moves)
+            }
+            TreeError::MissingReply(moves, col) => {
+                write!(f, "no answer to column {} after {:?}", col + 1, moves)
+            }
+            TreeError::Unfinished(moves) => write!(f, "{:?} stops before a four", moves),
+            TreeError::MovesAfterWin(moves) => write!(f, "{:?} goes on after a four", moves),
+            TreeError::LoserWins(moves) => write!(f, "the loser makes four with {:?}", moves),
+        }
+    }
+}
+
+impl std::error::Error for TreeError {}
+
+// the file for a strategy from `ProofSearch::strategy`, `moves` being the position it proves
+pub fn format_tree(moves: &str, lines: &[Vec<u8>]) -> String {
+    let mut text = String::from("# the player to move wins\n");
+    text.push_str(&format!("position {}\n", moves));
+    for line in lines {
+        text.push_str(&moves_text(line));
+        text.push('\n');
+    }
+    text
+}
+
+// the winning strategy of the exact solver, in the same form as `ProofSearch::strategy`: the
+// quickest win wherever the winner is to move and every legal reply wherever the loser is.
+// None unless the player to move wins.
+pub fn solver_strategy(position: Board, table: &mut Table) -> Option<Vec<Vec<u8>>> {
+    if position.is_game_over() || solve(position, table).0 <= 0 {
+        return None;
+    }
+    let mut lines = Vec::new();
+    collect(position, table, &mut Vec::new(), &mut lines);
+    Some(lines)
+}
+
+// the winner is to move. Their best move keeps the win and brings the four closer, so none of
+// the replies can end the game.
+fn collect(position: Board, table: &mut Table, line: &mut Vec<u8>, lines: &mut Vec<Vec<u8>>) {
+    if position.can_win_next() {
+        let mut finished = line.clone();
+        finished.push(position.winning_move());
+        lines.push(finished);
+        return;
+    }
+    let (_, col) = best_move(position, table).unwrap();
+    let mut after = position;
+    after.play_col(col);
+    line.push(col);
+    for reply in after.legal_columns() {
+        let mut next_position = after;
+        next_position.play_col(reply);
+        line.push(reply);
+        collect(next_position, table, line, lines);
+        line.pop();
+    }
+    line.pop();
+}
+
+// checks a proof tree file, giving the number of lines it ends in a four
+pub fn verify(text: &str) -> Result<usize, TreeError> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+    let position = match lines.next() {
+        Some((_, line)) if line.starts_with("position") => {
+            Board::parse(&line["position".len()..]).map_err(TreeError::Position)?
+        }
+        _ => return Err(TreeError::MissingPosition),
+    };
+    if position.is_game_over() {
+        return Err(TreeError::GameOver);
+    }
+    let mut sequences = Vec::new();
+    for (number, line) in lines {
+        let moves: Option<Vec<u8>> = line
+            .chars()
+            .map(|c| match c.to_digit(10) {
+                Some(col) if col >= 1 && col <= Board::WIDTH as u32 => Some(col as u8 - 1),
+                _ => None,
+            })
+            .collect();
+        sequences.push(moves.ok_or(TreeError::InvalidLine(number))?);
+    }
+    if sequences.is_empty() {
+        return Err(TreeError::Unfinished(String::new()));
+    }
+    sequences.sort();
+    let sequences: Vec<&[u8]> = sequences.iter().map(|moves| moves.as_slice()).collect();
+    check(position, &sequences, 0)
+}
+
+// every sequence starts with the same `depth` moves, which lead to `position`
+fn check(position: Board, sequences: &[&[u8]], depth: usize) -> Result<usize, TreeError> {
+    let path = || moves_text(&sequences[0][..depth]);
+    if sequences.iter().any(|moves| moves.len() == depth) {
+        return Err(TreeError::Unfinished(path()));
+    }
+    // sorted, so lines that go on with the same move are next to each other
+    let mut branches: Vec<(u8, &[&[u8]])> = Vec::new();
+    let mut start = 0;
+    for end in 1..=sequences.len() {
+        if end == sequences.len() || sequences[end][depth] != sequences[start][depth] {
+            branches.push((sequences[start][depth], &sequences[start..end]));
+            start = end;
+        }
+    }
+    if depth % 2 == 0 {
+        if branches.len() > 1 {
+            return Err(TreeError::MoreThanOneMove(path()));
+        }
+    } else if let Some(col) = position
+        .legal_columns()
+        .find(|&col| branches.iter().all(|&(branch, _)| branch != col))
+    {
+        return Err(TreeError::MissingReply(path(), col));
+    } else if branches.is_empty() {
+        return Err(TreeError::Unfinished(path()));
+    }
+    let mut wins = 0;
+    for (col, branch) in branches {
+        if !position.can_play(col) {
+            return Err(TreeError::IllegalMove(path(), col));
+        }
+        let mut next = position;
+        next.play_col(col);
+        if next.last_move_won() {
+            if depth % 2 == 1 {
+                return Err(TreeError::LoserWins(moves_text(&branch[0][..=depth])));
+            }
+            if branch.iter().any(|moves| moves.len() > depth + 1) {
+                return Err(TreeError::MovesAfterWin(moves_text(&branch[0][..=depth])));
+            }
+            wins += branch.len();
+        } else {
+            wins += check(next, branch, depth + 1)?;
+        }
+    }
+    Ok(wins)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof::{ProofResult, ProofSearch};
+
+    #[test]
+    fn search_and_verify() {
+        let mut search = ProofSearch::new(1 << 24);
+        // wins in four and six moves
+        for moves in ["6234565372114562234", "146553265722551153124711743447"].iter() {
+            let position = Board::construct(moves);
+            assert_eq!(search.prove(position), ProofResult::Proved);
+            let lines = search.strategy(position).unwrap();
+            let text = format_tree(moves, &lines);
+            assert!(lines.len() > 1);
+            assert_eq!(verify(&text), Ok(lines.len()));
+            // leaving out any line breaks the proof
+            let text = format_tree(moves, &lines[1..]);
+            assert!(verify(&text).is_err());
+        }
+        let text = "# the player to move wins\nposition 73463244621316374157434\n2\n";
+        assert_eq!(verify(text), Ok(1));
+    }
+
+    #[test]
+    fn solver_trees() {
+        let mut table = Table::with_capacity(1 << 20);
+        for moves in [
+            "6234565372114562234",
+            "146553265722551153124711743447",
+            "73463244621316374157434",
+        ]
+        .iter()
+        {
+            let lines = solver_strategy(Board::construct(moves), &mut table).unwrap();
+            assert_eq!(verify(&format_tree(moves, &lines)), Ok(lines.len()));
+        }
+        // no tree unless the player to move wins
+        for moves in [
+            "257771314744647214174561",
+            "53233651363321727745271",
+            "1212121",
+        ]
+        .iter()
+        {
+            assert_eq!(solver_strategy(Board::construct(moves), &mut table), None);
+        }
+    }
+
+    #[test]
+    fn bad_trees() {
+        let position = "position 73463244621316374157434\n";
+        assert_eq!(verify("2\n"), Err(TreeError::MissingPosition));
+        assert_eq!(
+            verify("position 448\n"),
+            Err(TreeError::Position(MoveError::InvalidColumn(2)))
+        );
+        assert_eq!(
+            verify(&format!("{}2\n3x\n", position)),
+            Err(TreeError::InvalidLine(3))
+        );
+        assert_eq!(
+            verify(&format!("{}2\n1\n", position)),
+            Err(TreeError::MoreThanOneMove(String::new()))
+        );
+        // column 4 is full
+        assert_eq!(
+            verify(&format!("{}4\n", position)),
+            Err(TreeError::IllegalMove(String::new(), 3))
+        );
+        assert_eq!(
+            verify(&format!("{}21\n", position)),
+            Err(TreeError::MovesAfterWin("2".to_string()))
+        );
+        // not a win, and then only one of the replies is answered
+        assert_eq!(
+            verify(&format!("{}1\n", position)),
+            Err(TreeError::Unfinished("1".to_string()))
+        );
+        assert!(matches!(
+            verify(&format!("{}12\n", position)),
+            Err(TreeError::MissingReply(_, _))
+        ));
+    }
+}
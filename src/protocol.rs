@@ -0,0 +1,328 @@
+// A line based protocol for driving the solver from other programs, loosely modelled on UCI.
+//
+//   position [moves]        set the position, moves are 1 indexed columns e.g. `position 4453`
+//   go [nodes N] [time MS]  solve the position, optionally giving up after N nodes or MS milliseconds
+//   analyze [nodes N] [time MS]
+//                           score every column
+//   stop                    interrupt the running search, reporting what it knows so far
+//   isready                 replies `readyok` once any running search has finished
+//   newgame                 clears the transposition table
+//   quit
+//
+// Replies are space separated key value pairs:
+//
+//   info score 2 nodes 5120 time 3
+//   info lowerbound -1 upperbound 4 nodes 100000 time 40
+//   info column 4 score 2
+//   bestmove 4
+//   error <message>
+//
+// Columns in replies are 1 indexed like the moves of `position`.
+
+use super::board::Board;
+use super::solver::{solve_bounded, Search};
+use super::table::Table;
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+type Output = Arc<Mutex<dyn Write + Send>>;
+
+pub struct Protocol {
+    position: Board,
+    // the table is handed to the search thread and given back when it finishes
+    table: Option<Table>,
+    search: Option<JoinHandle<Table>>,
+    stop: Arc<AtomicBool>,
+    output: Output,
+}
+
+impl Protocol {
+    pub fn new(table: Table, output: Output) -> Protocol {
+        Protocol {
+            position: Board::new(),
+            table: Some(table),
+            search: None,
+            stop: Arc::new(AtomicBool::new(false)),
+            output,
+        }
+    }
+
+    // reads commands until `quit` or the end of the input
+    pub fn run<R: BufRead>(&mut self, input: R) {
+        for line in input.lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+            if !self.handle(&line) {
+                break;
+            }
+        }
+        self.finish_search();
+    }
+
+    // returns false once the engine should quit
+    pub fn handle(&mut self, line: &str) -> bool {
+        let mut words = line.split_whitespace();
+        let command = match words.next() {
+            Some(command) => command,
+            None => return true,
+        };
+        let args: Vec<&str> = words.collect();
+        if command == "stop" {
+            self.stop.store(true, Ordering::Relaxed);
+            self.finish_search();
+            return true;
+        }
+        if command == "quit" {
+            self.stop.store(true, Ordering::Relaxed);
+            self.finish_search();
+            return false;
+        }
+        // everything else waits for the running search to complete
+        self.finish_search();
+        match command {
+            "position" => match Board::parse(&args.concat()) {
+                Ok(position) => self.position = position,
+                Err(error) => self.reply(format!("error {}", error)),
+            },
+            "go" | "analyze" => match parse_limits(&args) {
+                Ok((max_nodes, time)) => self.start_search(command == "analyze", max_nodes, time),
+                Err(error) => self.reply(format!("error {}", error)),
+            },
+            "isready" => self.reply(String::from("readyok")),
+            "newgame" => {
+                if let Some(table) = self.table.as_mut() {
+                    table.clear();
+                }
+            }
+            _ => self.reply(format!("error unknown command {}", command)),
+        }
+        true
+    }
+
+    fn reply(&self, line: String) {
+        let mut output = self.output.lock().unwrap();
+        let _ = writeln!(output, "{}", line);
+        let _ = output.flush();
+    }
+
+    fn finish_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.table = Some(search.join().unwrap());
+        }
+        self.stop.store(false, Ordering::Relaxed);
+    }
+
+    fn start_search(&mut self, per_column: bool, max_nodes: Option<u64>, time: Option<Duration>) {
+        let position = self.position;
+        let mut table = self.table.take().unwrap();
+        let output = self.output.clone();
+        let start = Instant::now();
+        let mut search = Search {
+            max_nodes,
+            deadline: time.map(|time| start + time),
+            stop: Some(self.stop.clone()),
+            ..Search::new()
+        };
+        self.search = Some(thread::spawn(move || {
+            let lines = if per_column {
+                analyze_lines(position, &mut table, &mut search)
+            } else {
+                go_lines(position, &mut table, &mut search)
+            };
+            let mut output = output.lock().unwrap();
+            for line in lines {
+                let _ = writeln!(output, "{}", line);
+            }
+            let _ = output.flush();
+            table
+        }));
+    }
+}
+
+fn parse_limits(args: &[&str]) -> Result<(Option<u64>, Option<Duration>), String> {
+    let mut max_nodes = None;
+    let mut time = None;
+    let mut args = args.iter();
+    while let Some(&key) = args.next() {
+        let value = args
+            .next()
+            .and_then(|value| value.parse::<u64>().ok())
+            .ok_or_else(|| format!("expected a number after {}", key))?;
+        match key {
+            "nodes" => max_nodes = Some(value),
+            "time" => time = Some(Duration::from_millis(value)),
+            _ => return Err(format!("unknown limit {}", key)),
+        }
+    }
+    Ok((max_nodes, time))
+}
+
+fn bounds(lower: i32, upper: i32) -> String {
+    if lower == upper {
+        format!("score {}", lower)
+    } else {
+        format!("lowerbound {} upperbound {}", lower, upper)
+    }
+}
+
+fn go_lines(position: Board, table: &mut Table, search: &mut Search) -> Vec<String> {
+    let start = Instant::now();
+    if position.is_game_over() {
+        return vec![String::from("bestmove none")];
+    }
+    let (lower, upper, action) = solve_bounded(position, table, search);
+    let action = if (0..Board::WIDTH as i32).contains(&action) {
+        action as u8
+    } else if lower == upper {
+        // every probe failed low so the bisection never settled on a move, find one that keeps the score
+        exact_move(position, lower, table, search)
+    } else {
+        fallback_move(position)
+    };
+    vec![
+        format!(
+            "info {} nodes {} time {}",
+            bounds(lower, upper),
+            search.nodes,
+            start.elapsed().as_millis()
+        ),
+        format!("bestmove {}", action + 1),
+    ]
+}
+
+fn analyze_lines(position: Board, table: &mut Table, search: &mut Search) -> Vec<String> {
+    let start = Instant::now();
+    if position.is_game_over() {
+        return vec![String::from("bestmove none")];
+    }
+    let mut lines = Vec::new();
+    let mut best: Option<(u8, i32)> = None;
+    for col in position.legal_columns() {
+        let (lower, upper) = if position.is_winning_move(col) {
+            let score = ((Board::WIDTH * Board::HEIGHT + 1 - position.nb_moves()) / 2) as i32;
+            (score, score)
+        } else {
+            let mut next_position = position;
+            next_position.play_col(col);
+            let (lower, upper, _action) = solve_bounded(next_position, table, search);
+            (-upper, -lower)
+        };
+        lines.push(format!("info column {} {}", col + 1, bounds(lower, upper)));
+        if best.map_or(true, |(_, score)| lower > score) {
+            best = Some((col, lower));
+        }
+        if search.aborted {
+            break;
+        }
+    }
+    let action = best.map_or_else(|| fallback_move(position), |(col, _)| col);
+    lines.push(format!(
+        "info nodes {} time {}",
+        search.nodes,
+        start.elapsed().as_millis()
+    ));
+    lines.push(format!("bestmove {}", action + 1));
+    lines
+}
+
+fn exact_move(position: Board, score: i32, table: &mut Table, search: &mut Search) -> u8 {
+    for col in position.legal_columns() {
+        let mut next_position = position;
+        next_position.play_col(col);
+        let (lower, upper, _action) = solve_bounded(next_position, table, search);
+        if search.aborted {
+            break;
+        }
+        if lower == upper && -lower == score {
+            return col;
+        }
+    }
+    fallback_move(position)
+}
+
+// something sensible to play when the search was stopped before finding a move
+fn fallback_move(position: Board) -> u8 {
+    if position.can_win_next() {
+        return position.winning_move();
+    }
+    let nonlosing = position.nonlosing_moves();
+    Board::columns(nonlosing)
+        .next()
+        .unwrap_or_else(|| position.possible_move())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn run(commands: &str) -> Vec<String> {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let output: Output = Arc::new(Mutex::new(Shared(buffer.clone())));
+        let mut protocol = Protocol::new(Table::with_capacity(65521), output);
+        protocol.run(Cursor::new(commands));
+        let text = String::from_utf8(buffer.lock().unwrap().clone()).unwrap();
+        text.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn go() {
+        let lines = run("position 73463244621316374157434\ngo\nquit\n");
+        assert!(lines[0].starts_with("info score 10 "), "{:?}", lines);
+        assert_eq!(lines[1], "bestmove 2");
+    }
+
+    #[test]
+    fn analyze() {
+        let lines = run("position 53233651363321727745271\nanalyze\n");
+        let best = lines
+            .iter()
+            .find(|line| line.starts_with("bestmove"))
+            .unwrap();
+        assert_eq!(best, "bestmove 4");
+        assert!(lines
+            .iter()
+            .any(|line| line.starts_with("info column 4 score")));
+    }
+
+    #[test]
+    fn stop() {
+        let lines = run("position\ngo\nstop\nisready\n");
+        assert!(lines[0].starts_with("info lowerbound"), "{:?}", lines);
+        assert!(lines[1].starts_with("bestmove"));
+        assert_eq!(lines[2], "readyok");
+    }
+
+    #[test]
+    fn limits() {
+        let lines = run("go nodes 1000\ngo time 1\ngo depth 3\n");
+        assert!(lines[0].starts_with("info lowerbound"), "{:?}", lines);
+        assert!(lines[2].starts_with("info lowerbound"), "{:?}", lines);
+        assert_eq!(lines[4], "error unknown limit depth");
+    }
+
+    #[test]
+    fn errors() {
+        let lines = run("position 449\nposition 1212121\ngo\nfly\n");
+        assert_eq!(lines[0], "error invalid column at move 3");
+        assert_eq!(lines[1], "bestmove none");
+        assert_eq!(lines[2], "error unknown command fly");
+    }
+}
@@ -0,0 +1,181 @@
+// "Find the win" puzzles: positions sampled from random or self-play games where exactly one
+// column wins, checked with the solver's per-column scores.
+
+use super::board::Board;
+use super::engine::{Engine, OnePly, RandomPlayer};
+use super::sampler::{PlyRangeError, Sampler};
+use super::solver::analyze;
+use super::table::Table;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Random,
+    // games between randomized one-ply engines, which take wins and avoid immediate losses
+    SelfPlay,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PuzzleConfig {
+    pub count: usize,
+    pub source: Source,
+    // inclusive ranges, plies within 0 to 42
+    pub plies: (u8, u8),
+    pub mate_in: (u8, u8),
+    pub difficulty: (u8, u8),
+    pub seed: u64,
+    // gives up after sampling this many positions
+    pub max_samples: usize,
+}
+
+impl Default for PuzzleConfig {
+    fn default() -> PuzzleConfig {
+        PuzzleConfig {
+            count: 10,
+            source: Source::SelfPlay,
+            plies: (16, 32),
+            mate_in: (2, 21),
+            difficulty: (1, 5),
+            seed: 0,
+            max_samples: 100_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Puzzle {
+    // 1 indexed
+    pub moves: String,
+    // 0 indexed column of the only winning move
+    pub solution: u8,
+    pub score: i32,
+    // moves of the winning side up to and including the four
+    pub mate_in: u8,
+    // 1 to 5
+    pub difficulty: u8,
+}
+
+impl Puzzle {
+    // a single line of JSON, columns 1 indexed
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"moves\":\"{}\",\"solution\":{},\"score\":{},\"mate_in\":{},\"difficulty\":{}}}",
+            self.moves,
+            self.solution + 1,
+            self.score,
+            self.mate_in,
+            self.difficulty
+        )
+    }
+}
+
+// the puzzle in the position reached by `moves`, if exactly one column wins
+pub fn inspect(moves: &str, table: &mut Table) -> Option<Puzzle> {
+    let position = Board::parse(moves).ok()?;
+    if position.is_game_over() {
+        return None;
+    }
+    let scores = analyze(position, table);
+    let mut winning = (0..Board::WIDTH).filter(|&col| scores[col as usize].is_some_and(|s| s > 0));
+    let solution = winning.next()?;
+    if winning.next().is_some() {
+        return None;
+    }
+    let score = scores[solution as usize].unwrap();
+    let win_now = ((Board::WIDTH * Board::HEIGHT + 1 - position.nb_moves()) / 2) as i32;
+    let mate_in = (win_now - score + 1) as u8;
+    // longer wins are harder, and so are positions where plenty of other columns look safe
+    let safe = scores.iter().filter(|s| s.is_some_and(|s| s == 0)).count();
+    let difficulty = (mate_in.div_ceil(2) + u8::from(safe >= 3)).clamp(1, 5);
+    Some(Puzzle {
+        moves: moves.chars().filter(|c| !c.is_whitespace()).collect(),
+        solution,
+        score,
+        mate_in,
+        difficulty,
+    })
+}
+
+fn within(value: u8, (min, max): (u8, u8)) -> bool {
+    min <= value && value <= max
+}
+
+// up to `config.count` distinct puzzles matching the filters
+pub fn generate(config: &PuzzleConfig, table: &mut Table) -> Result<Vec<Puzzle>, PlyRangeError> {
+    let engine: Box<dyn Engine> = match config.source {
+        Source::Random => Box::new(RandomPlayer::new(config.seed)),
+        Source::SelfPlay => Box::new(OnePly::randomized(config.seed)),
+    };
+    let mut sampler = Sampler::new(engine, config.plies, config.max_samples)?;
+    let mut puzzles = Vec::new();
+    while puzzles.len() < config.count {
+        let (moves, _) = match sampler.next() {
+            Some(sample) => sample,
+            None => break,
+        };
+        if let Some(puzzle) = inspect(&moves, table) {
+            if within(puzzle.mate_in, config.mate_in)
+                && within(puzzle.difficulty, config.difficulty)
+            {
+                puzzles.push(puzzle);
+            }
+        }
+    }
+    Ok(puzzles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inspect_positions() {
+        let mut table = Table::with_capacity(1 << 20);
+        let puzzle = inspect("73463244621316374157434", &mut table).unwrap();
+        assert_eq!((puzzle.solution, puzzle.score, puzzle.mate_in), (1, 10, 1));
+        assert_eq!(
+            puzzle.to_json(),
+            r#"{"moves":"73463244621316374157434","solution":2,"score":10,"mate_in":1,"difficulty":1}"#
+        );
+        // lost positions aren't puzzles
+        assert_eq!(inspect("257771314744647214174561", &mut table), None);
+        assert_eq!(inspect("1212121", &mut table), None);
+    }
+
+    #[test]
+    fn generate_puzzles() {
+        let mut table = Table::with_capacity(1 << 20);
+        let config = PuzzleConfig {
+            count: 3,
+            plies: (26, 32),
+            mate_in: (2, 21),
+            seed: 3,
+            max_samples: 2000,
+            ..PuzzleConfig::default()
+        };
+        let puzzles = generate(&config, &mut table).unwrap();
+        assert_eq!(puzzles.len(), 3);
+        for puzzle in puzzles.iter() {
+            assert!(puzzle.mate_in >= 2);
+            let position = Board::construct(&puzzle.moves);
+            let scores = analyze(position, &mut table);
+            let winning: Vec<u8> = (0..Board::WIDTH)
+                .filter(|&col| scores[col as usize].is_some_and(|s| s > 0))
+                .collect();
+            assert_eq!(winning, vec![puzzle.solution]);
+        }
+        assert_ne!(puzzles[0].moves, puzzles[1].moves);
+
+        let config = PuzzleConfig {
+            plies: (30, 20),
+            ..PuzzleConfig::default()
+        };
+        assert_eq!(
+            generate(&config, &mut table),
+            Err(PlyRangeError {
+                min: 30,
+                max: 20,
+                limit: 42
+            })
+        );
+    }
+}
@@ -0,0 +1,202 @@
+// Python bindings, only built with the `python` feature. Columns are 1 indexed to match move strings.
+//
+//   import thimblerigger
+//   table = thimblerigger.Table()
+//   board = thimblerigger.Board("4453")
+//   score, column = thimblerigger.solve(board, table)
+//   scores = thimblerigger.analyze(board, table)
+
+use super::arena::Outcome;
+use super::board::Board;
+use super::history::History;
+use super::solver::{analyze as analyze_columns, best_move};
+use super::table::Table;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::sync::{Mutex, OnceLock, PoisonError};
+
+#[pyclass(name = "Board", module = "thimblerigger", skip_from_py_object)]
+#[derive(Clone)]
+pub struct PyBoard {
+    history: History,
+}
+
+#[pymethods]
+impl PyBoard {
+    #[new]
+    #[pyo3(signature = (moves = ""))]
+    fn new(moves: &str) -> PyResult<PyBoard> {
+        let history =
+            History::from_moves(moves).map_err(|error| PyValueError::new_err(error.to_string()))?;
+        Ok(PyBoard { history })
+    }
+
+    fn play(&mut self, column: u8) -> PyResult<()> {
+        if self.history.board().is_game_over() {
+            return Err(PyValueError::new_err("game is already over"));
+        }
+        if !self.can_play(column) {
+            return Err(PyValueError::new_err(format!(
+                "can't play column {}",
+                column
+            )));
+        }
+        self.history.play(column - 1).unwrap();
+        Ok(())
+    }
+
+    // takes back the last move, returning its column or None at the start
+    fn undo(&mut self) -> Option<u8> {
+        self.history.undo().map(|col| col + 1)
+    }
+
+    // replays the last undone move, returning its column or None if there is nothing to redo
+    fn redo(&mut self) -> Option<u8> {
+        self.history.redo().map(|col| col + 1)
+    }
+
+    fn can_play(&self, column: u8) -> bool {
+        (1..=Board::WIDTH).contains(&column)
+            && !self.history.board().is_game_over()
+            && self.history.board().can_play(column - 1)
+    }
+
+    // u32 rather than u8 so that pyo3 hands back a list instead of bytes
+    fn legal_moves(&self) -> Vec<u32> {
+        (1..=Board::WIDTH)
+            .filter(|&col| self.can_play(col))
+            .map(u32::from)
+            .collect()
+    }
+
+    // "first", "second" or "draw" once the game is over, otherwise None
+    fn outcome(&self) -> Option<&'static str> {
+        Outcome::of(&self.history.board()).map(|outcome| match outcome {
+            Outcome::FirstWins => "first",
+            Outcome::SecondWins => "second",
+            Outcome::Draw => "draw",
+        })
+    }
+
+    // rows from top to bottom, X for the first player and O for the second, then column numbers
+    fn display(&self) -> String {
+        self.history.board().to_string()
+    }
+
+    #[getter]
+    fn moves(&self) -> String {
+        self.history.to_move_string()
+    }
+
+    #[getter]
+    fn num_moves(&self) -> u8 {
+        self.history.board().nb_moves()
+    }
+
+    fn copy(&self) -> PyBoard {
+        self.clone()
+    }
+
+    fn __str__(&self) -> String {
+        self.display()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Board(\"{}\")", self.history.to_move_string())
+    }
+}
+
+#[pyclass(name = "Table", module = "thimblerigger")]
+pub struct PyTable {
+    table: Table,
+}
+
+#[pymethods]
+impl PyTable {
+    #[new]
+    #[pyo3(signature = (capacity = None))]
+    fn new(capacity: Option<u64>) -> PyResult<PyTable> {
+        let capacity = capacity.unwrap_or(Table::CAPACITY);
+        if capacity == 0 {
+            return Err(PyValueError::new_err(
+                "the table needs a capacity of at least 1",
+            ));
+        }
+        Ok(PyTable {
+            table: Table::with_capacity(capacity),
+        })
+    }
+
+    fn clear(&mut self, py: Python<'_>) {
+        let table = &mut self.table;
+        py.detach(|| table.clear());
+    }
+}
+
+// used by every call that doesn't pass a table, allocated on the first one
+static DEFAULT_TABLE: OnceLock<Mutex<Table>> = OnceLock::new();
+
+fn with_table<T, F>(py: Python<'_>, table: Option<&Bound<'_, PyTable>>, f: F) -> T
+where
+    F: FnOnce(&mut Table) -> T + Send,
+    T: Send,
+{
+    match table {
+        Some(table) => {
+            let mut table = table.borrow_mut();
+            let table = &mut table.table;
+            py.detach(|| f(table))
+        }
+        None => py.detach(|| {
+            let table = DEFAULT_TABLE.get_or_init(|| Mutex::new(Table::new()));
+            f(&mut table.lock().unwrap_or_else(PoisonError::into_inner))
+        }),
+    }
+}
+
+fn playable(board: &PyBoard) -> PyResult<Board> {
+    let position = board.history.board();
+    if position.is_game_over() {
+        Err(PyValueError::new_err("game is already over"))
+    } else {
+        Ok(position)
+    }
+}
+
+// (score, column) for the player to move. Pass a Table to keep the positions solved apart from
+// other callers, otherwise the module's own table is used.
+#[pyfunction]
+#[pyo3(signature = (board, table = None))]
+fn solve(
+    py: Python<'_>,
+    board: &PyBoard,
+    table: Option<&Bound<'_, PyTable>>,
+) -> PyResult<(i32, u8)> {
+    let position = playable(board)?;
+    let (score, col) = with_table(py, table, |table| best_move(position, table)).unwrap();
+    Ok((score, col + 1))
+}
+
+// score of every column, None for full columns
+#[pyfunction]
+#[pyo3(signature = (board, table = None))]
+fn analyze(
+    py: Python<'_>,
+    board: &PyBoard,
+    table: Option<&Bound<'_, PyTable>>,
+) -> PyResult<Vec<Option<i32>>> {
+    let position = playable(board)?;
+    let scores = with_table(py, table, |table| analyze_columns(position, table));
+    Ok(scores.to_vec())
+}
+
+#[pymodule]
+fn thimblerigger(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyBoard>()?;
+    module.add_class::<PyTable>()?;
+    module.add_function(wrap_pyfunction!(solve, module)?)?;
+    module.add_function(wrap_pyfunction!(analyze, module)?)?;
+    module.add("WIDTH", Board::WIDTH)?;
+    module.add("HEIGHT", Board::HEIGHT)?;
+    Ok(())
+}
@@ -0,0 +1,232 @@
+// Game records shared by the archive, the web front end and the analysis tools.
+// See docs/game-record.md for the JSON layout, reading and writing JSON needs the `serde` feature.
+
+use super::arena::{self, Outcome};
+use super::board::{Board, MoveError};
+use std::fmt;
+
+pub const FORMAT: &str = "thimblerigger-game";
+pub const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Players {
+    pub first: String,
+    pub second: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Move {
+    // 1 indexed like move strings
+    pub column: u8,
+    // engine score of the position before the move, from the perspective of the player making it
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub score: Option<i32>,
+    // milliseconds since the unix epoch
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub time: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Game {
+    pub format: String,
+    pub version: u32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub players: Players,
+    // None while the game is in progress
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub result: Option<Outcome>,
+    pub moves: Vec<Move>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordError {
+    Json(String),
+    Format(String),
+    Version(u32),
+    Moves(MoveError),
+    // the result disagrees with the final position
+    Result,
+}
+
+impl fmt::Display for RecordError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordError::Json(error) => write!(f, "invalid json: {}", error),
+            RecordError::Format(format) => write!(f, "unknown format {}", format),
+            RecordError::Version(version) => write!(f, "unsupported version {}", version),
+            RecordError::Moves(error) => write!(f, "{}", error),
+            RecordError::Result => write!(f, "result doesn't match the final position"),
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+impl Game {
+    pub fn new(players: Players) -> Game {
+        Game {
+            format: String::from(FORMAT),
+            version: VERSION,
+            players,
+            result: None,
+            moves: Vec::new(),
+        }
+    }
+
+    // the moves as a digit string, the format `Board::construct` reads. The fields are public,
+    // so a column outside 1 to 7 is only caught here.
+    pub fn move_string(&self) -> Result<String, RecordError> {
+        self.moves
+            .iter()
+            .enumerate()
+            .map(|(index, m)| {
+                if (1..=Board::WIDTH).contains(&m.column) {
+                    Ok((b'0' + m.column) as char)
+                } else {
+                    Err(RecordError::Moves(MoveError::InvalidColumn(index)))
+                }
+            })
+            .collect()
+    }
+
+    pub fn from_move_string(moves: &str, players: Players) -> Result<Game, RecordError> {
+        let board = Board::parse(moves).map_err(RecordError::Moves)?;
+        let mut game = Game::new(players);
+        game.moves = moves
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| Move {
+                column: c as u8 - b'0',
+                score: None,
+                time: None,
+            })
+            .collect();
+        game.result = Outcome::of(&board);
+        Ok(game)
+    }
+
+    // the final position, checking that the moves are legal and the result is consistent
+    pub fn replay(&self) -> Result<Board, RecordError> {
+        if self.format != FORMAT {
+            return Err(RecordError::Format(self.format.clone()));
+        }
+        if self.version != VERSION {
+            return Err(RecordError::Version(self.version));
+        }
+        let board = Board::parse(&self.move_string()?).map_err(RecordError::Moves)?;
+        match (Outcome::of(&board), self.result) {
+            (Some(expected), Some(result)) if expected != result => Err(RecordError::Result),
+            (Some(_), None) => Err(RecordError::Result),
+            _ => Ok(board),
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &str) -> Result<Game, RecordError> {
+        let game: Game =
+            serde_json::from_str(json).map_err(|error| RecordError::Json(error.to_string()))?;
+        game.replay()?;
+        Ok(game)
+    }
+}
+
+impl From<&arena::GameRecord> for Game {
+    fn from(record: &arena::GameRecord) -> Game {
+        let players = Players {
+            first: record.first.clone(),
+            second: record.second.clone(),
+        };
+        let mut game = Game::from_move_string(&record.moves, players).unwrap();
+        game.result = Some(record.outcome);
+        game
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn players() -> Players {
+        Players {
+            first: String::from("alice"),
+            second: String::from("solver"),
+        }
+    }
+
+    #[test]
+    fn move_strings() {
+        let game = Game::from_move_string("1212121", players()).unwrap();
+        assert_eq!(game.move_string(), Ok(String::from("1212121")));
+        assert_eq!(game.result, Some(Outcome::FirstWins));
+        assert_eq!(game.replay(), Ok(Board::construct("1212121")));
+
+        let game = Game::from_move_string("4453", players()).unwrap();
+        assert_eq!(game.result, None);
+        assert_eq!(
+            Game::from_move_string("448", players()),
+            Err(RecordError::Moves(MoveError::InvalidColumn(2)))
+        );
+
+        let mut game = Game::from_move_string("4453", players()).unwrap();
+        for &column in [0, 8, 9, 208, 255].iter() {
+            game.moves[1].column = column;
+            let invalid = RecordError::Moves(MoveError::InvalidColumn(1));
+            assert_eq!(game.move_string(), Err(invalid.clone()));
+            assert_eq!(game.replay(), Err(invalid));
+        }
+    }
+
+    #[test]
+    fn inconsistent_result() {
+        let mut game = Game::from_move_string("1212121", players()).unwrap();
+        game.result = Some(Outcome::Draw);
+        assert_eq!(game.replay(), Err(RecordError::Result));
+        game.result = None;
+        assert_eq!(game.replay(), Err(RecordError::Result));
+
+        // resignations leave an unfinished position with a result
+        let mut game = Game::from_move_string("4453", players()).unwrap();
+        game.result = Some(Outcome::SecondWins);
+        assert!(game.replay().is_ok());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json() {
+        let mut game = Game::from_move_string("12121", players()).unwrap();
+        game.moves[0].score = Some(1);
+        game.moves[0].time = Some(1_600_000_000_000);
+        let json = game.to_json();
+        assert_eq!(Game::from_json(&json), Ok(game));
+
+        let json = r#"{"format":"thimblerigger-game","version":1,"result":"first",
+            "moves":[{"column":1},{"column":2},{"column":1},{"column":2},
+                     {"column":1},{"column":2},{"column":1}]}"#;
+        let game = Game::from_json(json).unwrap();
+        assert_eq!(game.move_string(), Ok(String::from("1212121")));
+        assert_eq!(game.players, Players::default());
+
+        let json = json.replace("\"first\"", "\"second\"");
+        assert_eq!(Game::from_json(&json), Err(RecordError::Result));
+        let json = json.replace("\"column\":1}", "\"column\":200}");
+        assert!(matches!(
+            Game::from_json(&json),
+            Err(RecordError::Moves(MoveError::InvalidColumn(0)))
+        ));
+        assert!(matches!(Game::from_json("{}"), Err(RecordError::Json(_))));
+    }
+}
@@ -0,0 +1,113 @@
+// A deliberately plain minimax with no pruning, table or move ordering, scored like `solve`.
+// It's far too slow for anything but positions near the end of the game, and is only there
+// to check the real solver against.
+
+use super::board::Board;
+
+const SQUARES: u8 = Board::WIDTH * Board::HEIGHT;
+
+// score of a position that isn't over yet for the player to move
+pub fn minimax(position: &Board) -> i32 {
+    if position.nb_moves() == SQUARES {
+        return 0;
+    }
+    let mut best = i32::MIN;
+    for col in 0..Board::WIDTH {
+        if position.can_play(col) {
+            best = best.max(column_score(position, col));
+        }
+    }
+    best
+}
+
+// score of playing `col`, which must be playable
+pub fn column_score(position: &Board, col: u8) -> i32 {
+    let mut next = *position;
+    next.play_col(col);
+    if next.last_move_won() {
+        ((SQUARES + 1 - position.nb_moves()) / 2) as i32
+    } else {
+        -minimax(&next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::Rng;
+    use crate::solver::{analyze, solve, solve_weak, WeakScore};
+    use crate::table::Table;
+
+    // a random game stopped after `plies` moves, or None if it ended first
+    fn random_position(rng: &mut Rng, plies: u8) -> Option<Board> {
+        let mut board = Board::new();
+        while board.nb_moves() < plies {
+            let legal: Vec<u8> = board.legal_columns().collect();
+            board.play_col(legal[rng.below(legal.len() as u32) as usize]);
+            if board.last_move_won() {
+                return None;
+            }
+        }
+        Some(board)
+    }
+
+    #[test]
+    fn known_positions() {
+        assert_eq!(
+            minimax(&Board::construct("6244176744414631153673221313256735767")),
+            3
+        );
+        assert_eq!(
+            minimax(&Board::construct("7311667277111227321526632533376645")),
+            -4
+        );
+    }
+
+    #[test]
+    fn matches_solve() {
+        let mut rng = Rng::new(44);
+        // one table for every position, so stale bounds from earlier positions would show up
+        let mut table = Table::with_capacity(65521);
+        let mut weak_table = Table::with_capacity(65521);
+        let mut checked = 0;
+        while checked < 3000 {
+            let plies = 33 + rng.below(9) as u8;
+            let position = match random_position(&mut rng, plies) {
+                Some(position) => position,
+                None => continue,
+            };
+            let score = minimax(&position);
+            assert_eq!(solve(position, &mut table).0, score, "{:?}", position);
+            assert_eq!(solve_weak(position, &mut weak_table), WeakScore::of(score));
+            checked += 1;
+        }
+    }
+
+    #[test]
+    fn matches_analyze() {
+        let mut rng = Rng::new(7);
+        let mut table = Table::with_capacity(65521);
+        let mut checked = 0;
+        while checked < 500 {
+            let plies = 33 + rng.below(9) as u8;
+            let position = match random_position(&mut rng, plies) {
+                Some(position) => position,
+                None => continue,
+            };
+            let scores = analyze(position, &mut table);
+            for col in 0..Board::WIDTH {
+                let expected = if position.can_play(col) {
+                    Some(column_score(&position, col))
+                } else {
+                    None
+                };
+                assert_eq!(
+                    scores[col as usize], expected,
+                    "{:?} column {}",
+                    position, col
+                );
+            }
+            checked += 1;
+        }
+    }
+}
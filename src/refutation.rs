@@ -0,0 +1,118 @@
+// Why a move loses: the opponent's winning line after it, with best defence, cut short once
+// the win is plain to see.
+
+use super::board::Board;
+use super::solver::{analyze, best_column};
+use super::table::Table;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnd {
+    // the last move of the line makes four
+    Four,
+    // the losing side is to move and can't stop every threat
+    DoubleThreat,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Refutation {
+    // score of the refuted move for the player making it, always negative
+    pub score: i32,
+    // 0 indexed columns starting with the opponent's reply
+    pub line: Vec<u8>,
+    pub end: LineEnd,
+}
+
+impl Refutation {
+    // 1 indexed like move strings
+    pub fn move_string(&self) -> String {
+        self.line.iter().map(|&col| (b'1' + col) as char).collect()
+    }
+}
+
+// None if the column can't be played or doesn't lose
+pub fn refute(position: Board, col: u8, table: &mut Table) -> Option<Refutation> {
+    if col >= Board::WIDTH || position.is_game_over() || !position.can_play(col) {
+        return None;
+    }
+    let scores = analyze(position, table);
+    let score = scores[col as usize]?;
+    if score >= 0 {
+        return None;
+    }
+    let mut board = position;
+    board.play_col(col);
+    let mut line = Vec::new();
+    // the winner is to move on even plies of the line
+    loop {
+        let winner = line.len() % 2 == 0;
+        if winner && board.can_win_next() {
+            line.push(board.winning_move());
+            return Some(Refutation {
+                score,
+                line,
+                end: LineEnd::Four,
+            });
+        }
+        if !winner && board.nonlosing_moves() == 0 {
+            return Some(Refutation {
+                score,
+                line,
+                end: LineEnd::DoubleThreat,
+            });
+        }
+        // the winner takes the quickest win and the loser holds out the longest
+        let next = best_column(&analyze(board, table)).unwrap();
+        board.play_col(next);
+        line.push(next);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn immediate_four() {
+        let mut table = Table::with_capacity(65521);
+        let position = Board::construct("53233651363321727745271");
+        let refutation = refute(position, 0, &mut table).unwrap();
+        assert_eq!(refutation.line, vec![3]);
+        assert_eq!(refutation.end, LineEnd::Four);
+        assert_eq!(refutation.move_string(), "4");
+        // blocking only delays it until a double threat
+        let refutation = refute(position, 3, &mut table).unwrap();
+        assert_eq!(refutation.end, LineEnd::DoubleThreat);
+        assert_eq!(refute(position, 7, &mut table), None);
+
+        // winning moves have nothing to refute
+        let position = Board::construct("73463244621316374157434");
+        assert_eq!(refute(position, 1, &mut table), None);
+    }
+
+    #[test]
+    fn forced_line() {
+        let mut table = Table::with_capacity(1 << 20);
+        let position = Board::construct("257771314744647214174561");
+        let scores = analyze(position, &mut table);
+        for col in position.legal_columns() {
+            let refutation = refute(position, col, &mut table).unwrap();
+            assert_eq!(Some(refutation.score), scores[col as usize]);
+            let mut board = position;
+            board.play_col(col);
+            for &next in refutation.line.iter() {
+                assert!(!board.is_game_over() && board.can_play(next));
+                board.play_col(next);
+            }
+            match refutation.end {
+                LineEnd::Four => {
+                    assert!(board.last_move_won());
+                    assert_eq!(refutation.line.len() % 2, 1);
+                }
+                LineEnd::DoubleThreat => {
+                    assert!(!board.is_game_over());
+                    assert_eq!(board.nonlosing_moves(), 0);
+                }
+            }
+        }
+    }
+}
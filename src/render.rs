@@ -0,0 +1,262 @@
+// Renderers for terminals and the web. The first player is red and the second yellow.
+// Plain text for logs and tests is the `Display` impl of `Board`.
+
+use super::board::Board;
+use std::fmt::Write;
+
+// what to mark on top of the stones
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Highlights {
+    // 0 indexed column of the last move, the top stone of that column is marked
+    pub last_move: Option<u8>,
+    pub winning_line: bool,
+    // empty squares that would complete a four for either player
+    pub threats: bool,
+}
+
+impl Highlights {
+    pub fn all(last_move: Option<u8>) -> Highlights {
+        Highlights {
+            last_move,
+            winning_line: true,
+            threats: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Empty,
+    // which players would win by playing here
+    Threat { first: bool, second: bool },
+    Stone { player: u8, marked: bool },
+}
+
+// cells from the top row down
+fn cells(board: &Board, highlights: &Highlights) -> Vec<Vec<Cell>> {
+    let stones = [board.stones(0), board.stones(1)];
+    let all = stones[0] | stones[1];
+    let mut marked = 0;
+    if let Some(col) = highlights.last_move {
+        if let Some(row) = (0..Board::HEIGHT)
+            .rev()
+            .find(|&row| all & Board::square_mask(col, row) != 0)
+        {
+            marked |= Board::square_mask(col, row);
+        }
+    }
+    if highlights.winning_line {
+        marked |= Board::fours(stones[0]) | Board::fours(stones[1]);
+    }
+    let threats = if highlights.threats && !board.is_game_over() {
+        [
+            Board::compute_winning_moves(stones[0], all),
+            Board::compute_winning_moves(stones[1], all),
+        ]
+    } else {
+        [0, 0]
+    };
+    (0..Board::HEIGHT)
+        .rev()
+        .map(|row| {
+            (0..Board::WIDTH)
+                .map(|col| {
+                    let bit = Board::square_mask(col, row);
+                    match board.owner(col, row) {
+                        Some(player) => Cell::Stone {
+                            player,
+                            marked: marked & bit != 0,
+                        },
+                        None if (threats[0] | threats[1]) & bit != 0 => Cell::Threat {
+                            first: threats[0] & bit != 0,
+                            second: threats[1] & bit != 0,
+                        },
+                        None => Cell::Empty,
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn labels(separator: &str) -> String {
+    let labels: Vec<String> = (1..=Board::WIDTH).map(|col| col.to_string()).collect();
+    labels.join(separator)
+}
+
+// coloured discs for terminals, marked stones are shown reversed
+pub fn ansi(board: &Board, highlights: &Highlights) -> String {
+    const RESET: &str = "\x1b[0m";
+    let mut output = String::new();
+    for row in cells(board, highlights) {
+        let row: Vec<String> = row
+            .iter()
+            .map(|cell| match *cell {
+                Cell::Empty => String::from("·"),
+                Cell::Threat { first, second } => {
+                    let colour = match (first, second) {
+                        (true, true) => "\x1b[35m",
+                        (true, false) => "\x1b[31m",
+                        _ => "\x1b[33m",
+                    };
+                    format!("{}×{}", colour, RESET)
+                }
+                Cell::Stone { player, marked } => {
+                    let colour = if player == 0 { "31" } else { "33" };
+                    let style = if marked { "1;7;" } else { "1;" };
+                    format!("\x1b[{}{}m●{}", style, colour, RESET)
+                }
+            })
+            .collect();
+        output.push_str(&row.join(" "));
+        output.push('\n');
+    }
+    output.push_str(&labels(" "));
+    output.push('\n');
+    output
+}
+
+// black and white discs for anywhere that handles unicode but not colour
+pub fn unicode(board: &Board, highlights: &Highlights) -> String {
+    let mut output = String::new();
+    for row in cells(board, highlights) {
+        let row: Vec<&str> = row
+            .iter()
+            .map(|cell| match *cell {
+                Cell::Empty => "·",
+                Cell::Threat { .. } => "×",
+                Cell::Stone { player: 0, marked } => {
+                    if marked {
+                        "◉"
+                    } else {
+                        "●"
+                    }
+                }
+                Cell::Stone { marked, .. } => {
+                    if marked {
+                        "◎"
+                    } else {
+                        "○"
+                    }
+                }
+            })
+            .collect();
+        output.push_str(&row.join(" "));
+        output.push('\n');
+    }
+    output.push_str(&labels(" "));
+    output.push('\n');
+    output
+}
+
+// a standalone svg document, marked stones get an outline and threats a small dot
+pub fn svg(board: &Board, highlights: &Highlights) -> String {
+    const CELL: u32 = 50;
+    const RADIUS: u32 = 20;
+    let width = CELL * Board::WIDTH as u32;
+    let height = CELL * Board::HEIGHT as u32;
+    let mut output = String::new();
+    let _ = writeln!(
+        output,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">",
+        width, height, width, height
+    );
+    let _ = writeln!(
+        output,
+        "  <rect width=\"{}\" height=\"{}\" rx=\"10\" fill=\"#1d4ed8\"/>",
+        width, height
+    );
+    for (y, row) in cells(board, highlights).iter().enumerate() {
+        for (x, cell) in row.iter().enumerate() {
+            let cx = x as u32 * CELL + CELL / 2;
+            let cy = y as u32 * CELL + CELL / 2;
+            let (fill, stroke) = match *cell {
+                Cell::Stone { player, marked } => (
+                    if player == 0 { "#dc2626" } else { "#facc15" },
+                    if marked { "#ffffff" } else { "none" },
+                ),
+                _ => ("#ffffff", "none"),
+            };
+            let _ = writeln!(
+                output,
+                "  <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\" stroke=\"{}\" stroke-width=\"4\"/>",
+                cx, cy, RADIUS, fill, stroke
+            );
+            if let Cell::Threat { first, second } = *cell {
+                let fill = match (first, second) {
+                    (true, true) => "#c026d3",
+                    (true, false) => "#dc2626",
+                    _ => "#facc15",
+                };
+                let _ = writeln!(
+                    output,
+                    "  <circle class=\"threat\" cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>",
+                    cx,
+                    cy,
+                    RADIUS / 3,
+                    fill
+                );
+            }
+        }
+    }
+    output.push_str("</svg>\n");
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unicode_highlights() {
+        let board = Board::construct("4453");
+        let plain = unicode(&board, &Highlights::default());
+        assert!(
+            plain.ends_with("· · ○ ● ● · ·\n1 2 3 4 5 6 7\n"),
+            "{}",
+            plain
+        );
+
+        let last = Highlights {
+            last_move: Some(2),
+            ..Highlights::default()
+        };
+        assert!(unicode(&board, &last).contains("· · ◎ ● ● · ·\n"));
+
+        // red threatens to finish the bottom row at either end
+        let threats = unicode(&Board::construct("44556"), &Highlights::all(None));
+        let expected = "· · · ○ ○ · ·\n· · × ● ● ● ×\n1 2 3 4 5 6 7\n";
+        assert!(threats.ends_with(expected), "{}", threats);
+    }
+
+    #[test]
+    fn winning_line() {
+        let board = Board::construct("1212121");
+        let highlights = Highlights {
+            winning_line: true,
+            ..Highlights::default()
+        };
+        let text = unicode(&board, &highlights);
+        assert_eq!(text.matches('◉').count(), 4, "{}", text);
+        assert_eq!(text.matches('○').count(), 3);
+        // no threats once the game is over
+        assert!(!unicode(&board, &Highlights::all(Some(0))).contains('×'));
+    }
+
+    #[test]
+    fn ansi_and_svg() {
+        let board = Board::construct("44556");
+        let text = ansi(&board, &Highlights::all(Some(5)));
+        assert_eq!(text.lines().count(), Board::HEIGHT as usize + 1);
+        assert_eq!(text.matches("\x1b[1;7;31m").count(), 1);
+        assert_eq!(text.matches("\x1b[31m×").count(), 2);
+
+        let svg = svg(&board, &Highlights::all(Some(5)));
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        let discs = Board::WIDTH as usize * Board::HEIGHT as usize;
+        assert_eq!(svg.matches("<circle ").count(), discs + 2);
+        assert_eq!(svg.matches("class=\"threat\"").count(), 2);
+        assert_eq!(svg.matches("stroke=\"#ffffff\"").count(), 1);
+    }
+}
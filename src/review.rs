@@ -0,0 +1,214 @@
+// Post-game review: every position of a game is solved and each move is marked as the best,
+// an inaccuracy (same result, but a slower win or a quicker loss) or a blunder (it changes the
+// theoretical result). Scores are for the player making the move, like `solve`.
+
+use super::board::{Board, MoveError};
+use super::solver::{analyze, best_column, solve};
+use super::table::Table;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quality {
+    Best,
+    Inaccuracy,
+    Blunder,
+}
+
+impl fmt::Display for Quality {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Quality::Best => "best",
+            Quality::Inaccuracy => "inaccuracy",
+            Quality::Blunder => "blunder",
+        };
+        f.pad(name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveReview {
+    // 0 indexed
+    pub column: u8,
+    pub score: i32,
+    pub best_score: i32,
+    // the move to play instead, None when the move played was the best
+    pub best_column: Option<u8>,
+    pub quality: Quality,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Review {
+    // moves before this were skipped
+    pub first: usize,
+    pub moves: Vec<MoveReview>,
+}
+
+impl Review {
+    // percentage for the first (0) or second (1) player, a best move counts fully and an
+    // inaccuracy half. None if they have no reviewed moves.
+    pub fn accuracy(&self, player: usize) -> Option<f64> {
+        let moves: Vec<&MoveReview> = self
+            .moves
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| (self.first + i) % 2 == player)
+            .map(|(_, m)| m)
+            .collect();
+        if moves.is_empty() {
+            return None;
+        }
+        let points: f64 = moves
+            .iter()
+            .map(|m| match m.quality {
+                Quality::Best => 1.0,
+                Quality::Inaccuracy => 0.5,
+                Quality::Blunder => 0.0,
+            })
+            .sum();
+        Some(100.0 * points / moves.len() as f64)
+    }
+}
+
+impl fmt::Display for Review {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, m) in self.moves.iter().enumerate() {
+            write!(
+                f,
+                "{:>3}. {} {:<10} {:>3}",
+                self.first + i + 1,
+                m.column + 1,
+                m.quality,
+                m.score
+            )?;
+            match m.best_column {
+                Some(col) => writeln!(f, "  best {} ({})", col + 1, m.best_score)?,
+                None => writeln!(f)?,
+            }
+        }
+        for (player, name) in ["first", "second"].iter().enumerate() {
+            if let Some(accuracy) = self.accuracy(player) {
+                writeln!(f, "{} player accuracy {:.1}%", name, accuracy)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn win_score(position: &Board) -> i32 {
+    ((Board::WIDTH * Board::HEIGHT + 1 - position.nb_moves()) / 2) as i32
+}
+
+// score of the position for the player to move, None once the game is over
+fn score(position: Board, table: &mut Table) -> Option<i32> {
+    if position.is_game_over() {
+        None
+    } else if position.can_win_next() {
+        Some(win_score(&position))
+    } else {
+        Some(solve(position, table).0)
+    }
+}
+
+// reviews the moves after the first `skip`, opening positions are slow to solve
+pub fn review(moves: &str, skip: usize, table: &mut Table) -> Result<Review, MoveError> {
+    Board::parse(moves)?;
+    let columns: Vec<u8> = moves
+        .chars()
+        .filter_map(|c| c.to_digit(10))
+        .map(|col| col as u8 - 1)
+        .collect();
+    let mut position = Board::new();
+    for &col in columns.iter().take(skip) {
+        position.play_col(col);
+    }
+    let mut reviews = Vec::new();
+    let mut best_score = score(position, table);
+    for &col in columns.iter().skip(skip) {
+        let best = best_score.unwrap();
+        let wins = position.is_winning_move(col);
+        let mut next_position = position;
+        next_position.play_col(col);
+        best_score = score(next_position, table);
+        let played = if wins {
+            win_score(&position)
+        } else {
+            // a full board is a draw
+            -best_score.unwrap_or(0)
+        };
+        let quality = if played == best {
+            Quality::Best
+        } else if played.signum() != best.signum() {
+            Quality::Blunder
+        } else {
+            Quality::Inaccuracy
+        };
+        let best_column = if quality == Quality::Best {
+            None
+        } else {
+            best_column(&analyze(position, table))
+        };
+        reviews.push(MoveReview {
+            column: col,
+            score: played,
+            best_score: best,
+            best_column,
+            quality,
+        });
+        position = next_position;
+    }
+    Ok(Review {
+        first: skip.min(columns.len()),
+        moves: reviews,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blunders() {
+        let mut table = Table::with_capacity(1 << 20);
+        let report = review("734632446213163741574342", 23, &mut table).unwrap();
+        assert_eq!(report.first, 23);
+        assert_eq!(report.moves.len(), 1);
+        let first = report.moves[0];
+        assert_eq!(first.column, 1);
+        assert_eq!((first.quality, first.score), (Quality::Best, 10));
+        assert_eq!(first.best_column, None);
+        assert_eq!(report.accuracy(0), None);
+        assert_eq!(report.accuracy(1), Some(100.0));
+
+        // missing the winning move throws the win away
+        let report = review("734632446213163741574347", 23, &mut table).unwrap();
+        assert_eq!(report.moves[0].quality, Quality::Blunder);
+        assert_eq!(report.moves[0].best_column, Some(1));
+        assert_eq!(report.accuracy(1), Some(0.0));
+        assert!(report.to_string().contains(" 24. 7 blunder"));
+    }
+
+    #[test]
+    fn quality() {
+        let mut table = Table::with_capacity(1 << 20);
+        let moves = "53233651363321727745271";
+        let position = Board::construct(moves);
+        let scores = analyze(position, &mut table);
+        let best = best_column(&scores).unwrap();
+        for col in position.legal_columns() {
+            let game = format!("{}{}", moves, col + 1);
+            let m = review(&game, 23, &mut table).unwrap().moves[0];
+            assert_eq!(Some(m.score), scores[col as usize]);
+            assert_eq!(m.best_score, scores[best as usize].unwrap());
+            let expected = if m.score == m.best_score {
+                Quality::Best
+            } else if (m.score < 0) != (m.best_score < 0) || (m.score == 0) != (m.best_score == 0) {
+                Quality::Blunder
+            } else {
+                Quality::Inaccuracy
+            };
+            assert_eq!(m.quality, expected);
+            assert_eq!(m.best_column.is_none(), m.quality == Quality::Best);
+        }
+        assert!(review("448", 0, &mut table).is_err());
+    }
+}
@@ -0,0 +1,133 @@
+// Positions for the generators: games played by an engine from the empty board, each stopped
+// at a ply in the range. Games that end early and positions an earlier game already reached
+// are skipped.
+
+use super::board::Board;
+use super::engine::Engine;
+use std::collections::HashSet;
+use std::fmt;
+
+// the ply range is backwards or goes past the deepest ply allowed, a full board for sampled
+// games
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlyRangeError {
+    pub min: u8,
+    pub max: u8,
+    pub limit: u8,
+}
+
+impl fmt::Display for PlyRangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid ply range {} to {}, expected 0 <= min <= max <= {}",
+            self.min, self.max, self.limit
+        )
+    }
+}
+
+impl std::error::Error for PlyRangeError {}
+
+// an inclusive range of moves played that ends by `limit`
+pub fn check_plies((min, max): (u8, u8), limit: u8) -> Result<(), PlyRangeError> {
+    if min > max || max > limit {
+        return Err(PlyRangeError { min, max, limit });
+    }
+    Ok(())
+}
+
+pub struct Sampler {
+    engine: Box<dyn Engine>,
+    plies: (u8, u8),
+    max_samples: usize,
+    samples: usize,
+    seen: HashSet<u64>,
+}
+
+impl Sampler {
+    // plays at most `max_samples` games, stopping each after between plies.0 and plies.1 moves
+    pub fn new(
+        engine: Box<dyn Engine>,
+        plies: (u8, u8),
+        max_samples: usize,
+    ) -> Result<Sampler, PlyRangeError> {
+        check_plies(plies, Board::WIDTH * Board::HEIGHT)?;
+        Ok(Sampler {
+            engine,
+            plies,
+            max_samples,
+            samples: 0,
+            seen: HashSet::new(),
+        })
+    }
+
+    // games played so far
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+}
+
+impl Iterator for Sampler {
+    // the moves, 1 indexed, and the position they reach
+    type Item = (String, Board);
+
+    fn next(&mut self) -> Option<(String, Board)> {
+        while self.samples < self.max_samples {
+            // vary the length so every game doesn't stop at the same ply
+            let (min, max) = self.plies;
+            let plies = min + (self.samples % (max - min + 1) as usize) as u8;
+            self.samples += 1;
+            let mut board = Board::new();
+            let mut moves = String::new();
+            while board.nb_moves() < plies && !board.is_game_over() {
+                let col = self.engine.best_move(board);
+                board.play_col(col);
+                moves.push((b'1' + col) as char);
+            }
+            if !board.is_game_over() && self.seen.insert(board.hash()) {
+                return Some((moves, board));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::RandomPlayer;
+
+    #[test]
+    fn positions() {
+        let sampler = Sampler::new(Box::new(RandomPlayer::new(4)), (10, 14), 200).unwrap();
+        let positions: Vec<(String, Board)> = sampler.collect();
+        assert!(!positions.is_empty() && positions.len() <= 200);
+        let keys: HashSet<u64> = positions.iter().map(|(_, board)| board.hash()).collect();
+        assert_eq!(keys.len(), positions.len());
+        for (moves, board) in positions.iter() {
+            assert!((10..=14).contains(&board.nb_moves()));
+            assert!(!board.is_game_over());
+            assert_eq!(Board::parse(moves), Ok(*board));
+        }
+
+        let mut sampler = Sampler::new(Box::new(RandomPlayer::new(4)), (0, 0), 5).unwrap();
+        assert_eq!(sampler.next(), Some((String::new(), Board::new())));
+        assert_eq!(sampler.next(), None);
+        assert_eq!(sampler.samples(), 5);
+    }
+
+    #[test]
+    fn invalid_ranges() {
+        for &plies in [(5, 4), (0, 43), (200, 255)].iter() {
+            assert_eq!(
+                Sampler::new(Box::new(RandomPlayer::new(0)), plies, 10).err(),
+                Some(PlyRangeError {
+                    min: plies.0,
+                    max: plies.1,
+                    limit: 42
+                })
+            );
+        }
+        assert!(Sampler::new(Box::new(RandomPlayer::new(0)), (42, 42), 10).is_ok());
+    }
+}
@@ -1,15 +1,66 @@
 use super::board::Board;
 use super::sort::MoveSort;
 use super::table::Table;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+// node counting and the limits that can interrupt a search
+#[derive(Default)]
+pub struct Search {
+    pub nodes: u64,
+    pub max_nodes: Option<u64>,
+    pub deadline: Option<Instant>,
+    pub stop: Option<Arc<AtomicBool>>,
+    pub aborted: bool,
+    // columns to try first when moves look equally good, `Board::exploration_order` if None
+    pub order: Option<[u8; Board::WIDTH as usize]>,
+}
+
+impl Search {
+    pub fn new() -> Search {
+        Search::default()
+    }
+
+    fn visit(&mut self) -> bool {
+        self.nodes += 1;
+        if let Some(max_nodes) = self.max_nodes {
+            if self.nodes > max_nodes {
+                self.aborted = true;
+            }
+        }
+        // checking the clock and the flag on every node is surprisingly expensive
+        if self.nodes & 1023 == 0 {
+            if let Some(deadline) = self.deadline {
+                if Instant::now() >= deadline {
+                    self.aborted = true;
+                }
+            }
+            if let Some(stop) = &self.stop {
+                if stop.load(Ordering::Relaxed) {
+                    self.aborted = true;
+                }
+            }
+        }
+        !self.aborted
+    }
+}
 
 pub fn solve(position: Board, table: &mut Table) -> (i32, i32) {
+    let (min, _max, action) = solve_bounded(position, table, &mut Search::new());
+    (min, action)
+}
+
+// like solve, but gives up once the search is aborted and returns the bounds
+// narrowed so far as (min, max, action). The score is exact when min == max.
+pub fn solve_bounded(position: Board, table: &mut Table, search: &mut Search) -> (i32, i32, i32) {
     let mut min = -((Board::WIDTH * Board::HEIGHT - position.nb_moves()) as i32) / 2;
     let mut max = ((Board::WIDTH * Board::HEIGHT + 1 - position.nb_moves()) as i32) / 2;
     let mut action = -100;
     if position.can_win_next() {
         let mut score = Board::HEIGHT * Board::WIDTH + 1 - position.nb_moves();
         score /= 2; // allows encoding for different players is symmetric
-        return (score as i32, position.winning_move() as i32);
+        return (score as i32, score as i32, position.winning_move() as i32);
     }
     while min < max {
         let mut med = min + (max - min) / 2;
@@ -18,7 +69,10 @@ pub fn solve(position: Board, table: &mut Table) -> (i32, i32) {
         } else if med >= 0 && max / 2 > med {
             med = max / 2;
         }
-        let (result, action_c) = negamax(position, table, med, med + 1);
+        let (result, action_c) = negamax_search(position, table, med, med + 1, search);
+        if search.aborted {
+            break;
+        }
         //println!("\t{}", action_c);
         if action_c != -1 {
             action = action_c;
@@ -29,11 +83,134 @@ pub fn solve(position: Board, table: &mut Table) -> (i32, i32) {
             min = result;
         }
     }
-    (min, action)
+    (min, max, action)
+}
+
+// score of every column from the perspective of the player to move, None if the column is full
+// or the game is already over
+pub fn analyze(position: Board, table: &mut Table) -> [Option<i32>; Board::WIDTH as usize] {
+    let mut scores = [None; Board::WIDTH as usize];
+    for col in position.legal_columns() {
+        if position.is_winning_move(col) {
+            let score = (Board::WIDTH * Board::HEIGHT + 1 - position.nb_moves()) / 2;
+            scores[col as usize] = Some(score as i32);
+            continue;
+        }
+        let mut next_position = position;
+        next_position.play_col(col);
+        let (score, _action) = solve(next_position, table);
+        scores[col as usize] = Some(-score);
+    }
+    scores
+}
+
+// the score and a column that gets it, None once the game is over. The column the search
+// comes back with is checked first, so usually only one child is solved on top of `solve`.
+pub fn best_move(position: Board, table: &mut Table) -> Option<(i32, u8)> {
+    if position.is_game_over() {
+        return None;
+    }
+    let (score, action) = solve(position, table);
+    let searched = (0..Board::WIDTH).filter(|&col| col as i32 == action && position.can_play(col));
+    for col in searched.chain(position.legal_columns()) {
+        let child = if position.is_winning_move(col) {
+            score
+        } else {
+            let mut next_position = position;
+            next_position.play_col(col);
+            -solve(next_position, table).0
+        };
+        if child == score {
+            return Some((score, col));
+        }
+    }
+    None
+}
+
+// column with the highest score, ties broken by exploration order
+pub fn best_column(scores: &[Option<i32>; Board::WIDTH as usize]) -> Option<u8> {
+    let mut best: Option<(u8, i32)> = None;
+    for &col in Board::exploration_order.iter() {
+        if let Some(score) = scores[col as usize] {
+            match best {
+                Some((_, best_score)) if best_score >= score => {}
+                _ => best = Some((col, score)),
+            }
+        }
+    }
+    best.map(|(col, _)| col)
+}
+
+// the result of a position without the distance to the end
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeakScore {
+    Loss,
+    Draw,
+    Win,
+}
+
+impl WeakScore {
+    pub fn of(score: i32) -> WeakScore {
+        match score.signum() {
+            -1 => WeakScore::Loss,
+            0 => WeakScore::Draw,
+            _ => WeakScore::Win,
+        }
+    }
+
+    pub fn flip(self) -> WeakScore {
+        match self {
+            WeakScore::Loss => WeakScore::Win,
+            WeakScore::Draw => WeakScore::Draw,
+            WeakScore::Win => WeakScore::Loss,
+        }
+    }
+}
+
+// win, draw or loss for the player to move. A [-1, 1] window only has to tell the sign of
+// the score apart, which prunes far more than the bisection in `solve`.
+pub fn solve_weak(position: Board, table: &mut Table) -> WeakScore {
+    if position.can_win_next() {
+        return WeakScore::Win;
+    }
+    let (score, _action) = negamax(position, table, -1, 1);
+    WeakScore::of(score)
+}
+
+// like analyze, but only the result of each column
+pub fn analyze_weak(
+    position: Board,
+    table: &mut Table,
+) -> [Option<WeakScore>; Board::WIDTH as usize] {
+    let mut scores = [None; Board::WIDTH as usize];
+    for col in position.legal_columns() {
+        if position.is_winning_move(col) {
+            scores[col as usize] = Some(WeakScore::Win);
+            continue;
+        }
+        let mut next_position = position;
+        next_position.play_col(col);
+        scores[col as usize] = Some(solve_weak(next_position, table).flip());
+    }
+    scores
 }
 
 // at least alpha, at most beta
-pub fn negamax(position: Board, table: &mut Table, mut alpha: i32, mut beta: i32) -> (i32, i32) {
+pub fn negamax(position: Board, table: &mut Table, alpha: i32, beta: i32) -> (i32, i32) {
+    negamax_search(position, table, alpha, beta, &mut Search::new())
+}
+
+// the result is meaningless once search.aborted is set
+pub fn negamax_search(
+    position: Board,
+    table: &mut Table,
+    mut alpha: i32,
+    mut beta: i32,
+    search: &mut Search,
+) -> (i32, i32) {
+    if !search.visit() {
+        return (alpha, -1);
+    }
     let possible = position.nonlosing_moves();
     //println!("{:b}", possible);
     if possible == 0 {
@@ -56,11 +233,10 @@ pub fn negamax(position: Board, table: &mut Table, mut alpha: i32, mut beta: i32
     }
 
     //let max = ((Board::WIDTH * Board::HEIGHT - 1 - position.nb_moves()) / 2) as i32;
-    let max;
-    match table.get(&position) {
-        Some(score) => max = score + Board::MIN_SCORE - 1,
-        None => max = ((Board::WIDTH * Board::HEIGHT - 1 - position.nb_moves()) / 2) as i32,
-    }
+    let max = match table.get(&position) {
+        Some(score) => score + Board::MIN_SCORE - 1,
+        None => ((Board::WIDTH * Board::HEIGHT - 1 - position.nb_moves()) / 2) as i32,
+    };
 
     if beta > max {
         beta = max;
@@ -69,21 +245,26 @@ pub fn negamax(position: Board, table: &mut Table, mut alpha: i32, mut beta: i32
         }
     }
 
+    // the sort puts the last of equally scored moves first
     let mut move_sort = MoveSort::new();
-    for i in (0..Board::WIDTH).rev() {
-        let action = possible & Board::col_mask(Board::exploration_order[i as usize]);
-        if action > 0 {
-            let value = position.action_score(action);
-            move_sort.insert(Board::exploration_order[i as usize], value);
+    let order = search.order.unwrap_or(Board::exploration_order);
+    for &col in order.iter().rev() {
+        if possible & Board::col_mask(col) == 0 {
+            continue;
         }
+        let value = position.action_score(possible & Board::col_mask(col));
+        move_sort.insert(col, value);
     }
 
     let mut best_action: i32 = -1;
     while move_sort.size > 0 {
         let action = move_sort.get_next();
         let mut next_position = position;
-        next_position.play_col(action);
-        let (mut score, _step) = negamax(next_position, table, -beta, -alpha);
+        next_position.play_from(possible, action);
+        let (mut score, _step) = negamax_search(next_position, table, -beta, -alpha, search);
+        if search.aborted {
+            return (alpha, -1);
+        }
         score = -score;
         if score >= beta {
             return (score, action as i32);
@@ -113,3 +294,46 @@ pub fn negamax(position: Board, table: &mut Table, mut alpha: i32, mut beta: i32
 
     (alpha, best_action)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weak_matches_strong() {
+        let mut table = Table::with_capacity(1 << 20);
+        let mut weak_table = Table::with_capacity(1 << 20);
+        for moves in [
+            "73463244621316374157434",
+            "53233651363321727745271",
+            "257771314744647214174561",
+            "16757111614166325423452322",
+        ]
+        .iter()
+        {
+            let position = Board::construct(moves);
+            let (score, _action) = solve(position, &mut table);
+            assert_eq!(
+                solve_weak(position, &mut weak_table),
+                WeakScore::of(score),
+                "{}",
+                moves
+            );
+            let scores = analyze(position, &mut table);
+            let (best, col) = best_move(position, &mut table).unwrap();
+            assert_eq!(Some(best), scores[col as usize], "{}", moves);
+            assert_eq!(scores.iter().max(), Some(&Some(best)), "{}", moves);
+            let weak = analyze_weak(position, &mut weak_table);
+            for col in 0..Board::WIDTH as usize {
+                assert_eq!(
+                    weak[col],
+                    scores[col].map(WeakScore::of),
+                    "{} {}",
+                    moves,
+                    col
+                );
+            }
+        }
+        assert_eq!(best_move(Board::construct("1212121"), &mut table), None);
+    }
+}
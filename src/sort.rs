@@ -12,6 +12,12 @@ pub struct MoveSort {
     pub size: usize,
 }
 
+impl Default for MoveSort {
+    fn default() -> MoveSort {
+        MoveSort::new()
+    }
+}
+
 impl MoveSort {
     pub fn new() -> MoveSort {
         let moves = [MoveNode {
@@ -24,10 +30,6 @@ impl MoveSort {
         }
     }
 
-    fn clear(&mut self) {
-        self.size = 0;
-    }
-
     pub fn insert(&mut self, action: u8, value: i32) {
         self.size += 1;
         // increase the size
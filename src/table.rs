@@ -25,19 +25,34 @@ impl TableNode {
 
 pub struct Table {
     contents: Vec<TableNode>,
+    capacity: u64,
     add_count: i32,
     get_count: i32,
 }
 
+impl Default for Table {
+    fn default() -> Table {
+        Table::new()
+    }
+}
+
 impl Table {
-    const CAPACITY: u64 = 8388593 * 2;
+    pub const CAPACITY: u64 = 8388593 * 2;
 
     pub fn new() -> Table {
+        Table::with_capacity(Table::CAPACITY)
+    }
+
+    // smaller tables are handy for comparing engines or running many solvers at once. There is
+    // always at least one entry, positions are placed by their key modulo the capacity.
+    pub fn with_capacity(capacity: u64) -> Table {
+        let capacity = capacity.max(1);
         let empty = TableNode {
             node: TableNode::HASH_MASK,
         };
         Table {
-            contents: vec![empty; Table::CAPACITY as usize],
+            contents: vec![empty; capacity as usize],
+            capacity,
             add_count: 0,
             get_count: 0,
         }
@@ -45,13 +60,13 @@ impl Table {
 
     pub fn add(&mut self, position: &Board, score: i32) {
         self.add_count += 1;
-        let index = (position.hash() % Table::CAPACITY) as usize;
+        let index = (position.hash() % self.capacity) as usize;
         self.contents[index] = TableNode::new(position.hash(), score as i8);
     }
 
     pub fn get(&mut self, position: &Board) -> Option<i32> {
         self.get_count += 1;
-        let index = (position.hash() % Table::CAPACITY) as usize;
+        let index = (position.hash() % self.capacity) as usize;
         let node = self.contents[index];
         if node.get_hash() == position.hash() {
             Some(node.get_value() as i32)
@@ -61,8 +76,10 @@ impl Table {
     }
 
     pub fn clear(&mut self) {
-        let empty = TableNode { node: 0 };
-        for i in 0..Table::CAPACITY {
+        let empty = TableNode {
+            node: TableNode::HASH_MASK,
+        };
+        for i in 0..self.capacity {
             self.contents[i as usize] = empty;
         }
     }
@@ -88,6 +105,10 @@ mod board_tests {
         table.add(&board, 10);
         assert_eq!(table.get(&board), Some(10));
 
+        let mut tiny = Table::with_capacity(0);
+        tiny.add(&board, 20);
+        assert_eq!(tiny.get(&board), Some(20));
+
         table.results();
     }
 }
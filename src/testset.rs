@@ -0,0 +1,170 @@
+// Generates the test suites `bench` runs, in the layout of Pascal Pons' Test_L<level>_R<rating>
+// files: one "<moves> <score>" line per position. The level is how far into the game the
+// position is and the rating how many moves are left before the game ends with perfect play.
+
+use super::board::Board;
+use super::engine::RandomPlayer;
+use super::sampler::Sampler;
+use super::solver::solve;
+use super::table::Table;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const SQUARES: u8 = Board::WIDTH * Board::HEIGHT;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Suite {
+    // 3 is the end of the game, 2 the middle and 1 the beginning
+    pub level: u8,
+    // 1 is easy, 2 medium and 3 hard
+    pub rating: u8,
+}
+
+// the suites `bench` expects, the other combinations can't happen
+pub const SUITES: [Suite; 6] = [
+    Suite::new(3, 1),
+    Suite::new(2, 1),
+    Suite::new(2, 2),
+    Suite::new(1, 1),
+    Suite::new(1, 2),
+    Suite::new(1, 3),
+];
+
+impl Suite {
+    pub const fn new(level: u8, rating: u8) -> Suite {
+        Suite { level, rating }
+    }
+
+    pub fn name(&self) -> String {
+        format!("Test_L{}_R{}", self.level, self.rating)
+    }
+
+    // inclusive range of moves played
+    pub fn plies(&self) -> (u8, u8) {
+        match self.level {
+            3 => (29, SQUARES - 1),
+            2 => (15, 28),
+            _ => (0, 14),
+        }
+    }
+
+    // inclusive range of moves left
+    pub fn remaining(&self) -> (u8, u8) {
+        match self.rating {
+            1 => (1, 13),
+            2 => (14, 27),
+            _ => (28, SQUARES),
+        }
+    }
+}
+
+// moves left in the game, both players included, if it's played out perfectly from a position
+// with the given score
+pub fn remaining_moves(position: &Board, score: i32) -> u8 {
+    let nb_moves = position.nb_moves();
+    let win_now = ((SQUARES + 1 - nb_moves) / 2) as i32;
+    if score > 0 {
+        (2 * (win_now - score) + 1) as u8
+    } else if score < 0 {
+        // the opponent wins, on their own moves one ply later
+        let win_next = ((SQUARES - nb_moves) / 2) as i32;
+        (2 * (win_next + score) + 2) as u8
+    } else {
+        SQUARES - nb_moves
+    }
+}
+
+// write_suites gives up on a suite after this many games per position asked for
+pub const SAMPLES_PER_POSITION: usize = 1000;
+
+// up to `count` distinct random positions in the suite with their scores, from at most
+// `max_samples` games. The same seed gives the same positions.
+pub fn generate(
+    suite: Suite,
+    count: usize,
+    seed: u64,
+    max_samples: usize,
+    table: &mut Table,
+) -> Vec<(String, i32)> {
+    let (min_plies, _) = suite.plies();
+    let (min_remaining, max_remaining) = suite.remaining();
+    if min_remaining > SQUARES - min_plies {
+        return Vec::new();
+    }
+    let engine = Box::new(RandomPlayer::new(seed));
+    // the suite's ranges are always valid
+    let mut sampler = Sampler::new(engine, suite.plies(), max_samples).unwrap();
+    let mut positions = Vec::new();
+    while positions.len() < count {
+        let (moves, board) = match sampler.next() {
+            Some(sample) => sample,
+            None => break,
+        };
+        let score = solve(board, table).0;
+        let remaining = remaining_moves(&board, score);
+        if min_remaining <= remaining && remaining <= max_remaining {
+            positions.push((moves, score));
+        }
+    }
+    positions
+}
+
+// writes up to `count` positions of every suite into `directory`, each suite with its own seed,
+// and gives the number written for each
+pub fn write_suites(directory: &Path, count: usize, seed: u64) -> io::Result<Vec<usize>> {
+    fs::create_dir_all(directory)?;
+    let mut table = Table::new();
+    let max_samples = count.saturating_mul(SAMPLES_PER_POSITION);
+    let mut written = Vec::new();
+    for (i, suite) in SUITES.iter().enumerate() {
+        let seed = seed.wrapping_add(i as u64);
+        let positions = generate(*suite, count, seed, max_samples, &mut table);
+        let mut text = String::new();
+        for (moves, score) in positions.iter() {
+            text.push_str(&format!("{} {}\n", moves, score));
+        }
+        fs::write(directory.join(suite.name()), text)?;
+        written.push(positions.len());
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remaining() {
+        // wins next move
+        let position = Board::construct("73463244621316374157434");
+        assert_eq!(remaining_moves(&position, 10), 1);
+        // a move from each side, then the opponent wins on their eighth move from here
+        let position = Board::construct("257771314744647214174561");
+        assert_eq!(remaining_moves(&position, -2), 16);
+        assert_eq!(remaining_moves(&position, -9), 2);
+        assert_eq!(remaining_moves(&Board::new(), 0), 42);
+    }
+
+    #[test]
+    fn end_game_suite() {
+        let mut table = Table::with_capacity(1 << 20);
+        let suite = SUITES[0];
+        assert_eq!(suite.name(), "Test_L3_R1");
+        let positions = generate(suite, 20, 5, 1000, &mut table);
+        assert_eq!(positions.len(), 20);
+        assert_eq!(positions, generate(suite, 20, 5, 1000, &mut table));
+        assert_ne!(positions, generate(suite, 20, 6, 1000, &mut table));
+        for (moves, score) in positions.iter() {
+            let position = Board::parse(moves).unwrap();
+            assert!(!position.is_game_over());
+            assert!(position.nb_moves() > 28);
+            assert_eq!(solve(position, &mut table).0, *score);
+            assert!(remaining_moves(&position, *score) < 14);
+        }
+        // middle games can't last another 28 moves
+        assert!(generate(Suite::new(2, 3), 1, 0, 1000, &mut table).is_empty());
+        // stops after the last game even when too few positions were found
+        assert!(generate(suite, 20, 5, 10, &mut table).len() <= 10);
+    }
+}
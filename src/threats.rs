@@ -0,0 +1,228 @@
+// Threat analysis in the terms players use: odd and even threats, double threats and who
+// controls zugzwang. Rows are 0 indexed from the bottom, so row 0 is the first (odd) row.
+// Players are 0 for the first and 1 for the second, like `Board::owner`.
+//
+// The zugzwang rules are the classic rules of thumb, good for explaining a position but
+// the solver has the final word.
+
+use super::board::Board;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Parity {
+    Odd,
+    Even,
+}
+
+// an empty square that would complete a four for `player`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Threat {
+    pub player: u8,
+    pub col: u8,
+    pub row: u8,
+    pub parity: Parity,
+    // the square can be played right now
+    pub immediate: bool,
+}
+
+// threats the opponent can't stop both of
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DoubleThreat {
+    pub player: u8,
+    // two or more playable threats, or one playable threat with another right above it
+    pub threats: Vec<Threat>,
+    pub stacked: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ZugzwangRule {
+    // the first player has an odd threat and the second no even threat
+    OddThreat,
+    // the second player has an even threat and the first no odd threat
+    EvenThreat,
+    // the first player's odd threat beats the second player's even threats. Neither counts
+    // with an opponent threat lower in its column, so these are always in different columns.
+    OddOverEven,
+}
+
+// which player should win the fight for the last squares and the threats that decide it.
+// A threat only counts if the opponent has no threat lower down the same column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Zugzwang {
+    pub winner: u8,
+    pub rule: ZugzwangRule,
+    pub threats: Vec<Threat>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ThreatAnalysis {
+    // both players, by column then row
+    pub threats: Vec<Threat>,
+    pub double_threats: Vec<DoubleThreat>,
+    pub zugzwang: Option<Zugzwang>,
+}
+
+impl Board {
+    pub fn threat_squares(&self, player: u8) -> Vec<Threat> {
+        let all = self.stones(0) | self.stones(1);
+        let squares = Board::compute_winning_moves(self.stones(player), all);
+        let possible = self.possible();
+        let mut threats = Vec::new();
+        for col in 0..Board::WIDTH {
+            for row in 0..Board::HEIGHT {
+                if squares & Board::square_mask(col, row) == 0 {
+                    continue;
+                }
+                threats.push(Threat {
+                    player,
+                    col,
+                    row,
+                    parity: if row % 2 == 0 {
+                        Parity::Odd
+                    } else {
+                        Parity::Even
+                    },
+                    immediate: possible & Board::square_mask(col, row) != 0,
+                });
+            }
+        }
+        threats
+    }
+
+    pub fn double_threat(&self, player: u8) -> Option<DoubleThreat> {
+        let threats = self.threat_squares(player);
+        let immediate: Vec<Threat> = threats.iter().copied().filter(|t| t.immediate).collect();
+        if immediate.len() >= 2 {
+            return Some(DoubleThreat {
+                player,
+                threats: immediate,
+                stacked: false,
+            });
+        }
+        for low in immediate {
+            let high = threats
+                .iter()
+                .find(|t| t.col == low.col && t.row == low.row + 1);
+            if let Some(&high) = high {
+                return Some(DoubleThreat {
+                    player,
+                    threats: vec![low, high],
+                    stacked: true,
+                });
+            }
+        }
+        None
+    }
+
+    pub fn zugzwang(&self) -> Option<Zugzwang> {
+        let first = self.threat_squares(0);
+        let second = self.threat_squares(1);
+        // a threat is spoiled by an opponent's threat below it in the same column
+        let counts = |threat: &Threat, opponent: &[Threat]| {
+            !threat.immediate
+                && !opponent
+                    .iter()
+                    .any(|t| t.col == threat.col && t.row < threat.row)
+        };
+        let odd: Vec<Threat> = first
+            .iter()
+            .copied()
+            .filter(|t| t.parity == Parity::Odd && counts(t, &second))
+            .collect();
+        let even: Vec<Threat> = second
+            .iter()
+            .copied()
+            .filter(|t| t.parity == Parity::Even && counts(t, &first))
+            .collect();
+        match (odd.is_empty(), even.is_empty()) {
+            (false, true) => Some(Zugzwang {
+                winner: 0,
+                rule: ZugzwangRule::OddThreat,
+                threats: odd,
+            }),
+            (true, false) => Some(Zugzwang {
+                winner: 1,
+                rule: ZugzwangRule::EvenThreat,
+                threats: even,
+            }),
+            // a column can't hold both, the lower threat would have spoiled the other
+            (false, false) => Some(Zugzwang {
+                winner: 0,
+                rule: ZugzwangRule::OddOverEven,
+                threats: odd.into_iter().chain(even).collect(),
+            }),
+            (true, true) => None,
+        }
+    }
+
+    pub fn threat_analysis(&self) -> ThreatAnalysis {
+        let mut threats = self.threat_squares(0);
+        threats.extend(self.threat_squares(1));
+        threats.sort_by_key(|t| (t.col, t.row));
+        let double_threats = (0..2).filter_map(|p| self.double_threat(p)).collect();
+        ThreatAnalysis {
+            threats,
+            double_threats,
+            zugzwang: self.zugzwang(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn double_threats() {
+        // open three on the bottom row
+        let board = Board::construct("44556");
+        let threats = board.threat_squares(0);
+        assert_eq!(threats.len(), 2);
+        assert!(threats
+            .iter()
+            .all(|t| t.row == 0 && t.parity == Parity::Odd && t.immediate));
+        let double = board.double_threat(0).unwrap();
+        assert!(!double.stacked);
+        assert_eq!(double.threats, threats);
+        assert_eq!(board.double_threat(1), None);
+
+        // the second player threatens the bottom square of column 4 and the one above it
+        let board = Board::construct("1225236327157276");
+        let double = board.double_threat(1).unwrap();
+        assert!(double.stacked);
+        let squares: Vec<(u8, u8)> = double.threats.iter().map(|t| (t.col, t.row)).collect();
+        assert_eq!(squares, vec![(3, 0), (3, 1)]);
+        assert_eq!(board.threat_analysis().double_threats, vec![double]);
+    }
+
+    #[test]
+    fn zugzwang() {
+        let zugzwang = Board::construct("56255224454").zugzwang().unwrap();
+        assert_eq!(zugzwang.winner, 0);
+        assert_eq!(zugzwang.rule, ZugzwangRule::OddThreat);
+        assert_eq!(zugzwang.threats.len(), 1);
+        assert_eq!((zugzwang.threats[0].col, zugzwang.threats[0].row), (2, 2));
+
+        let zugzwang = Board::construct("5235721753").zugzwang().unwrap();
+        assert_eq!(zugzwang.winner, 1);
+        assert_eq!(zugzwang.rule, ZugzwangRule::EvenThreat);
+        assert_eq!(zugzwang.threats[0].parity, Parity::Even);
+
+        // the second player's even threat lower in the same column spoils the odd threat
+        let board = Board::construct("427624555176674");
+        assert_eq!(board.threat_squares(0)[0].parity, Parity::Odd);
+        let zugzwang = board.zugzwang().unwrap();
+        assert_eq!(zugzwang.rule, ZugzwangRule::EvenThreat);
+        assert_eq!((zugzwang.threats[0].col, zugzwang.threats[0].row), (2, 1));
+
+        assert_eq!(Board::new().zugzwang(), None);
+        assert_eq!(Board::new().threat_analysis().threats, vec![]);
+    }
+}
@@ -0,0 +1,292 @@
+// Solved positions for training value and policy networks, written as a NumPy .npy file of
+// packed 25 byte records that `numpy.load` reads as a structured array:
+//
+//   first    <u8     stones of the first player, bit col * 7 + row with row 0 at the bottom
+//   second   <u8     stones of the second player
+//   to_move  u1      0 when the first player is to move, 1 for the second
+//   score    i1      exact score for the player to move, as `solve` gives it
+//   columns  i1 x 7  score of playing each column, -128 when the column is full
+
+use super::board::Board;
+use super::engine::{Engine, OnePly, RandomPlayer};
+use super::sampler::{check_plies, PlyRangeError, Sampler};
+use super::solver::analyze;
+use super::table::Table;
+use std::collections::HashSet;
+use std::io::{self, Write};
+
+pub const FULL_COLUMN: i8 = i8::MIN;
+pub const RECORD_SIZE: usize = 18 + Board::WIDTH as usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sampling {
+    Random,
+    // games between randomized one-ply engines
+    SelfPlay,
+    // every position reachable within the ply range, up to MAX_EXHAUSTIVE_PLY
+    Exhaustive,
+}
+
+// a whole level of positions is kept in memory, and there are about 1.7 million distinct
+// positions after 10 moves and 4.6 million after 11
+pub const MAX_EXHAUSTIVE_PLY: u8 = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportConfig {
+    pub sampling: Sampling,
+    // at most this many positions before augmentation
+    pub count: usize,
+    // inclusive range of moves played, within 0 to 42
+    pub plies: (u8, u8),
+    pub seed: u64,
+    // adds the mirror image of every position that isn't symmetric
+    pub mirror: bool,
+    // random and self-play sampling give up after this many games
+    pub max_samples: usize,
+}
+
+impl Default for ExportConfig {
+    fn default() -> ExportConfig {
+        ExportConfig {
+            sampling: Sampling::SelfPlay,
+            count: 10_000,
+            plies: (16, 40),
+            seed: 0,
+            mirror: false,
+            max_samples: 1_000_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Record {
+    pub board: Board,
+    pub score: i8,
+    pub columns: [i8; Board::WIDTH as usize],
+}
+
+impl Record {
+    // the position can't be over yet
+    pub fn solve(board: Board, table: &mut Table) -> Record {
+        let mut columns = [FULL_COLUMN; Board::WIDTH as usize];
+        for (col, score) in analyze(board, table).iter().enumerate() {
+            if let Some(score) = score {
+                columns[col] = *score as i8;
+            }
+        }
+        // the position is worth its best move
+        let score = *columns.iter().max().unwrap();
+        Record {
+            board,
+            score,
+            columns,
+        }
+    }
+
+    pub fn mirror(&self) -> Record {
+        let mut columns = self.columns;
+        columns.reverse();
+        Record {
+            board: self.board.mirror(),
+            score: self.score,
+            columns,
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; RECORD_SIZE] {
+        let mut bytes = [0; RECORD_SIZE];
+        bytes[0..8].copy_from_slice(&self.board.stones(0).to_le_bytes());
+        bytes[8..16].copy_from_slice(&self.board.stones(1).to_le_bytes());
+        bytes[16] = self.board.nb_moves() % 2;
+        bytes[17] = self.score as u8;
+        for (byte, &score) in bytes[18..].iter_mut().zip(self.columns.iter()) {
+            *byte = score as u8;
+        }
+        bytes
+    }
+}
+
+// every position in the ply range that isn't over, in order of plies
+fn enumerate(plies: (u8, u8), count: usize) -> Vec<Board> {
+    let mut positions = Vec::new();
+    let mut level = vec![Board::new()];
+    for ply in 0..=plies.1 {
+        if ply >= plies.0 {
+            positions.extend(level.iter().take(count - positions.len()));
+            if positions.len() == count {
+                break;
+            }
+        }
+        if ply == plies.1 {
+            break;
+        }
+        let mut seen = HashSet::new();
+        let mut next = Vec::new();
+        for board in level.iter() {
+            for col in board.legal_columns() {
+                if board.is_winning_move(col) {
+                    continue;
+                }
+                let mut child = *board;
+                child.play_col(col);
+                if child.nb_moves() < Board::WIDTH * Board::HEIGHT && seen.insert(child.hash()) {
+                    next.push(child);
+                }
+            }
+        }
+        level = next;
+    }
+    positions
+}
+
+// the sampled positions solved, duplicates (by position key) left out
+pub fn sample(config: &ExportConfig, table: &mut Table) -> Result<Vec<Record>, PlyRangeError> {
+    let limit = match config.sampling {
+        Sampling::Exhaustive => MAX_EXHAUSTIVE_PLY,
+        _ => Board::WIDTH * Board::HEIGHT,
+    };
+    check_plies(config.plies, limit)?;
+    let positions: Vec<Board> = match config.sampling {
+        Sampling::Exhaustive => enumerate(config.plies, config.count),
+        _ => {
+            let engine: Box<dyn Engine> = match config.sampling {
+                Sampling::Random => Box::new(RandomPlayer::new(config.seed)),
+                _ => Box::new(OnePly::randomized(config.seed)),
+            };
+            Sampler::new(engine, config.plies, config.max_samples)?
+                .map(|(_, board)| board)
+                .take(config.count)
+                .collect()
+        }
+    };
+    let mut seen: HashSet<u64> = positions.iter().map(|board| board.hash()).collect();
+    let mut records = Vec::new();
+    for board in positions {
+        let record = Record::solve(board, table);
+        records.push(record);
+        if config.mirror && seen.insert(board.mirror().hash()) {
+            records.push(record.mirror());
+        }
+    }
+    Ok(records)
+}
+
+// the .npy version 1.0 header for `count` records
+fn npy_header(count: usize) -> Vec<u8> {
+    let mut dict = format!(
+        "{{'descr': [('first', '<u8'), ('second', '<u8'), ('to_move', '|u1'), \
+         ('score', '|i1'), ('columns', '|i1', ({},))], 'fortran_order': False, \
+         'shape': ({},), }}",
+        Board::WIDTH,
+        count
+    );
+    // the data has to start on a multiple of 64 bytes, counting the 10 byte preamble and the
+    // newline ending the header
+    while (10 + dict.len() + 1) % 64 != 0 {
+        dict.push(' ');
+    }
+    dict.push('\n');
+    let mut header = b"\x93NUMPY\x01\x00".to_vec();
+    header.extend_from_slice(&(dict.len() as u16).to_le_bytes());
+    header.extend_from_slice(dict.as_bytes());
+    header
+}
+
+pub fn write_npy<W: Write>(records: &[Record], mut output: W) -> io::Result<()> {
+    output.write_all(&npy_header(records.len()))?;
+    for record in records {
+        output.write_all(&record.to_bytes())?;
+    }
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::solve;
+
+    #[test]
+    fn records() {
+        let mut table = Table::with_capacity(1 << 20);
+        let board = Board::construct("73463244621316374157434");
+        let record = Record::solve(board, &mut table);
+        assert_eq!(record.score, 10);
+        assert_eq!(record.score as i32, solve(board, &mut table).0);
+        assert_eq!(record.columns[1], 10);
+        let mirrored = record.mirror();
+        assert_eq!(mirrored.columns[5], 10);
+        assert_eq!(Record::solve(board.mirror(), &mut table), mirrored);
+
+        let bytes = record.to_bytes();
+        assert_eq!(bytes[0..8], board.stones(0).to_le_bytes());
+        assert_eq!(bytes[8..16], board.stones(1).to_le_bytes());
+        assert_eq!((bytes[16], bytes[17]), (1, 10));
+        assert_eq!(bytes[18 + 1], 10);
+        // the fourth column is full
+        assert_eq!(record.columns[3], FULL_COLUMN);
+        assert_eq!(bytes[18 + 3], 0x80);
+    }
+
+    #[test]
+    fn npy_file() {
+        let mut table = Table::with_capacity(1 << 20);
+        let config = ExportConfig {
+            sampling: Sampling::Random,
+            count: 20,
+            plies: (30, 36),
+            seed: 2,
+            mirror: true,
+            ..ExportConfig::default()
+        };
+        let records = sample(&config, &mut table).unwrap();
+        assert!(records.len() > 20 && records.len() <= 40);
+        let keys: HashSet<u64> = records.iter().map(|record| record.board.hash()).collect();
+        assert_eq!(keys.len(), records.len());
+        for record in records.iter() {
+            assert!((30..=36).contains(&record.board.nb_moves()));
+            assert_eq!(record.score as i32, solve(record.board, &mut table).0);
+        }
+
+        let mut file = Vec::new();
+        write_npy(&records, &mut file).unwrap();
+        assert_eq!(&file[..8], b"\x93NUMPY\x01\x00");
+        let header = u16::from_le_bytes([file[8], file[9]]) as usize;
+        assert_eq!((10 + header) % 64, 0);
+        let dict = std::str::from_utf8(&file[10..10 + header]).unwrap();
+        assert!(dict.ends_with(" \n"));
+        assert!(dict.contains(&format!("'shape': ({},)", records.len())));
+        assert_eq!(file.len(), 10 + header + RECORD_SIZE * records.len());
+        assert_eq!(
+            file[10 + header..10 + header + RECORD_SIZE],
+            records[0].to_bytes()
+        );
+
+        for &plies in [(36, 30), (30, 43)].iter() {
+            let config = ExportConfig { plies, ..config };
+            assert!(sample(&config, &mut table).is_err());
+        }
+    }
+
+    #[test]
+    fn exhaustive() {
+        let positions = enumerate((0, 4), usize::MAX);
+        // 1 + 7 + 49 + 238 + 1120 distinct positions, none of them over
+        assert_eq!(positions.len(), 1415);
+        assert_eq!(enumerate((3, 4), usize::MAX).len(), 238 + 1120);
+        assert_eq!(enumerate((2, 4), 10).len(), 10);
+        assert_eq!(enumerate((0, 0), 0).len(), 0);
+
+        let config = ExportConfig {
+            sampling: Sampling::Exhaustive,
+            ..ExportConfig::default()
+        };
+        assert_eq!(
+            sample(&config, &mut Table::with_capacity(1)).err(),
+            Some(PlyRangeError {
+                min: 16,
+                max: 40,
+                limit: MAX_EXHAUSTIVE_PLY
+            })
+        );
+    }
+}